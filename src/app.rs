@@ -4,7 +4,7 @@ use std::collections::VecDeque;
 
 use std::time::Instant;
 use std::time::Duration;
-use nalgebra::Vector2;
+use nalgebra::{Matrix3, Vector2, Vector3};
 //use ratatui::crossterm;
 use ratatui::widgets::canvas::Points;
 // Imports for ratatui.
@@ -23,15 +23,33 @@ use ratatui::{
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
 
 use crate::robo;
+use crate::localization::{metrics::Metrics, Localizer};
 use robo::RobotAccess;
 
 
 // Consts for Ratatui.
 const SIZE_RINGBUFF_DIST: usize = 60;
-
-// erstellt mit create_map.py.
+// Change of the wheel velocity per key press.
+const VELO_STEP: f64 = 1.;
+// Belief cells below this part of the most likely cell are not drawn.
+const BELIEF_DRAW_MIN: f64 = 0.01;
+// Added to the covariance when we switch the localizer. A very sharp estimate would fall
+// between the cells of the histogram filter.
+const SWITCH_MIN_VAR: (f64, f64) = (0.25, 0.05);
+
+// Points in screen coordinates, for the canvas.
+type ScreenPoints = Vec<(f64, f64)>;
+
+// erstellt mit create_map.py. Built into the binary so it runs without the assets, the Tiled
+// files can be loaded at runtime with --map (see tiled.rs).
 pub const WALL_SMALL: [(f64, f64); 441] = [(39.0, 0.0), (39.0, 1.0), (39.0, 2.0), (39.0, 3.0), (39.0, 4.0), (39.0, 5.0), (39.0, 6.0), (39.0, 7.0), (39.0, 8.0), (39.0, 9.0), (39.0, 10.0), (39.0, 11.0), (39.0, 12.0), (39.0, 13.0), (39.0, 14.0), (39.0, 15.0), (39.0, 16.0), (39.0, 17.0), (39.0, 18.0), (39.0, 19.0), (39.0, 20.0), (39.0, 21.0), (39.0, 22.0), (39.0, 23.0), (39.0, 24.0), (39.0, 25.0), (39.0, 26.0), (39.0, 27.0), (39.0, 28.0), (39.0, 29.0), (39.0, 30.0), (39.0, 31.0), (39.0, 32.0), (39.0, 33.0), (39.0, 34.0), (39.0, 35.0), (39.0, 36.0), (39.0, 37.0), (39.0, 38.0), (39.0, 39.0), (38.0, 0.0), (38.0, 29.0), (38.0, 30.0), (38.0, 31.0), (38.0, 32.0), (38.0, 39.0), (37.0, 0.0), (37.0, 28.0), (37.0, 29.0), (37.0, 30.0), (37.0, 31.0), (37.0, 39.0), (36.0, 0.0), (36.0, 27.0), (36.0, 28.0), (36.0, 29.0), (36.0, 30.0), (36.0, 39.0), (35.0, 0.0), (35.0, 26.0), (35.0, 27.0), (35.0, 28.0), (35.0, 29.0), (35.0, 39.0), (34.0, 0.0), (34.0, 5.0), (34.0, 6.0), (34.0, 7.0), (34.0, 17.0), (34.0, 18.0), (34.0, 19.0), (34.0, 25.0), (34.0, 26.0), (34.0, 27.0), (34.0, 28.0), (34.0, 39.0), (33.0, 0.0), (33.0, 5.0), (33.0, 6.0), (33.0, 7.0), (33.0, 17.0), (33.0, 18.0), (33.0, 19.0), (33.0, 24.0), (33.0, 25.0), (33.0, 26.0), (33.0, 27.0), (33.0, 39.0), (32.0, 0.0), (32.0, 5.0), (32.0, 6.0), (32.0, 7.0), (32.0, 17.0), (32.0, 18.0), (32.0, 19.0), (32.0, 23.0), (32.0, 24.0), (32.0, 25.0), (32.0, 26.0), (32.0, 30.0), (32.0, 31.0), (32.0, 32.0), (32.0, 33.0), (32.0, 34.0), (32.0, 39.0), (31.0, 0.0), (31.0, 6.0), (31.0, 22.0), (31.0, 23.0), (31.0, 24.0), (31.0, 25.0), (31.0, 30.0), (31.0, 31.0), (31.0, 32.0), (31.0, 33.0), (31.0, 34.0), (31.0, 39.0), (30.0, 0.0), (30.0, 6.0), (30.0, 21.0), (30.0, 22.0), (30.0, 23.0), (30.0, 24.0), (30.0, 30.0), (30.0, 31.0), (30.0, 32.0), (30.0, 33.0), (30.0, 34.0), (30.0, 39.0), (29.0, 0.0), (29.0, 6.0), (29.0, 20.0), (29.0, 21.0), (29.0, 22.0), (29.0, 23.0), (29.0, 30.0), (29.0, 31.0), (29.0, 32.0), (29.0, 33.0), (29.0, 34.0), (29.0, 39.0), (28.0, 0.0), (28.0, 6.0), (28.0, 20.0), (28.0, 21.0), (28.0, 22.0), (28.0, 30.0), (28.0, 31.0), (28.0, 32.0), (28.0, 33.0), (28.0, 34.0), (28.0, 39.0), (27.0, 0.0), (27.0, 6.0), (27.0, 10.0), (27.0, 11.0), (27.0, 12.0), (27.0, 13.0), (27.0, 14.0), (27.0, 15.0), (27.0, 39.0), (26.0, 0.0), (26.0, 6.0), (26.0, 10.0), (26.0, 39.0), (25.0, 0.0), (25.0, 6.0), (25.0, 10.0), (25.0, 39.0), (24.0, 0.0), (24.0, 6.0), (24.0, 10.0), (24.0, 39.0), (23.0, 0.0), (23.0, 6.0), (23.0, 10.0), (23.0, 14.0), (23.0, 15.0), (23.0, 16.0), (23.0, 17.0), (23.0, 18.0), (23.0, 19.0), (23.0, 20.0), (23.0, 21.0), (23.0, 22.0), (23.0, 23.0), (23.0, 24.0), (23.0, 25.0), (23.0, 33.0), (23.0, 39.0), (22.0, 0.0), (22.0, 6.0), (22.0, 10.0), (22.0, 14.0), (22.0, 33.0), (22.0, 39.0), (21.0, 0.0), (21.0, 6.0), (21.0, 10.0), (21.0, 14.0), (21.0, 33.0), (21.0, 39.0), (20.0, 0.0), (20.0, 6.0), (20.0, 10.0), (20.0, 14.0), (20.0, 33.0), (20.0, 39.0), (19.0, 0.0), (19.0, 6.0), (19.0, 14.0), (19.0, 18.0), (19.0, 19.0), (19.0, 20.0), (19.0, 21.0), (19.0, 22.0), (19.0, 23.0), (19.0, 32.0), (19.0, 39.0), (18.0, 0.0), (18.0, 6.0), (18.0, 14.0), (18.0, 18.0), (18.0, 19.0), (18.0, 20.0), (18.0, 21.0), (18.0, 22.0), (18.0, 23.0), (18.0, 31.0), (18.0, 32.0), (18.0, 39.0), (17.0, 0.0), (17.0, 6.0), (17.0, 7.0), (17.0, 14.0), (17.0, 18.0), (17.0, 19.0), (17.0, 20.0), (17.0, 21.0), (17.0, 22.0), (17.0, 23.0), (17.0, 28.0), (17.0, 29.0), (17.0, 30.0), (17.0, 31.0), (17.0, 39.0), (16.0, 0.0), (16.0, 6.0), (16.0, 7.0), (16.0, 8.0), (16.0, 14.0), (16.0, 18.0), (16.0, 19.0), (16.0, 20.0), (16.0, 21.0), (16.0, 22.0), (16.0, 23.0), (16.0, 27.0), (16.0, 28.0), (16.0, 39.0), (15.0, 0.0), (15.0, 7.0), (15.0, 8.0), (15.0, 9.0), (15.0, 18.0), (15.0, 19.0), (15.0, 20.0), (15.0, 21.0), (15.0, 22.0), (15.0, 23.0), (15.0, 27.0), (15.0, 39.0), (14.0, 0.0), (14.0, 8.0), (14.0, 9.0), (14.0, 10.0), (14.0, 18.0), (14.0, 19.0), (14.0, 20.0), (14.0, 21.0), (14.0, 22.0), (14.0, 23.0), (14.0, 33.0), (14.0, 34.0), (14.0, 35.0), (14.0, 39.0), (13.0, 0.0), (13.0, 4.0), (13.0, 9.0), (13.0, 10.0), (13.0, 11.0), (13.0, 18.0), (13.0, 19.0), (13.0, 20.0), (13.0, 21.0), (13.0, 22.0), (13.0, 23.0), (13.0, 29.0), (13.0, 30.0), (13.0, 31.0), (13.0, 32.0), (13.0, 33.0), (13.0, 35.0), (13.0, 39.0), (12.0, 0.0), (12.0, 4.0), (12.0, 10.0), (12.0, 11.0), (12.0, 15.0), (12.0, 16.0), (12.0, 17.0), (12.0, 18.0), (12.0, 19.0), (12.0, 20.0), (12.0, 21.0), (12.0, 22.0), (12.0, 23.0), (12.0, 29.0), (12.0, 35.0), (12.0, 39.0), (11.0, 0.0), (11.0, 4.0), (11.0, 11.0), (11.0, 15.0), (11.0, 23.0), (11.0, 35.0), (11.0, 36.0), (11.0, 39.0), (10.0, 0.0), (10.0, 4.0), (10.0, 11.0), (10.0, 15.0), (10.0, 23.0), (10.0, 36.0), (10.0, 39.0), (9.0, 0.0), (9.0, 4.0), (9.0, 11.0), (9.0, 15.0), (9.0, 23.0), (9.0, 39.0), (8.0, 0.0), (8.0, 4.0), (8.0, 11.0), (8.0, 15.0), (8.0, 16.0), (8.0, 17.0), (8.0, 18.0), (8.0, 19.0), (8.0, 23.0), (8.0, 28.0), (8.0, 29.0), (8.0, 39.0), (7.0, 0.0), (7.0, 4.0), (7.0, 11.0), (7.0, 15.0), (7.0, 16.0), (7.0, 17.0), (7.0, 18.0), (7.0, 19.0), (7.0, 23.0), (7.0, 29.0), (7.0, 30.0), (7.0, 31.0), (7.0, 32.0), (7.0, 33.0), (7.0, 34.0), (7.0, 39.0), (6.0, 0.0), (6.0, 4.0), (6.0, 11.0), (6.0, 23.0), (6.0, 34.0), (6.0, 39.0), (5.0, 0.0), (5.0, 4.0), (5.0, 5.0), (5.0, 6.0), (5.0, 7.0), (5.0, 8.0), (5.0, 9.0), (5.0, 10.0), (5.0, 11.0), (5.0, 23.0), (5.0, 34.0), (5.0, 39.0), (4.0, 0.0), (4.0, 23.0), (4.0, 34.0), (4.0, 39.0), (3.0, 0.0), (3.0, 32.0), (3.0, 33.0), (3.0, 34.0), (3.0, 39.0), (2.0, 0.0), (2.0, 39.0), (1.0, 0.0), (1.0, 23.0), (1.0, 39.0), (0.0, 0.0), (0.0, 1.0), (0.0, 2.0), (0.0, 3.0), (0.0, 4.0), (0.0, 5.0), (0.0, 6.0), (0.0, 7.0), (0.0, 8.0), (0.0, 9.0), (0.0, 10.0), (0.0, 11.0), (0.0, 12.0), (0.0, 13.0), (0.0, 14.0), (0.0, 15.0), (0.0, 16.0), (0.0, 17.0), (0.0, 18.0), (0.0, 19.0), (0.0, 20.0), (0.0, 21.0), (0.0, 22.0), (0.0, 23.0), (0.0, 24.0), (0.0, 25.0), (0.0, 26.0), (0.0, 27.0), (0.0, 28.0), (0.0, 29.0), (0.0, 30.0), (0.0, 31.0), (0.0, 32.0), (0.0, 33.0), (0.0, 34.0), (0.0, 35.0), (0.0, 36.0), (0.0, 37.0), (0.0, 38.0), (0.0, 39.0)];
 
+// The small map as wall cells, the tests run on it.
+#[cfg(test)]
+pub fn small_map() -> Vec<Vector2<f64>> {
+    WALL_SMALL.iter().map(|(x, y)| Vector2::new(*x, *y)).collect()
+}
+
 pub const WALL_BIG: [(f64,f64); 2805] = [(99.0, 0.0), (99.0, 1.0), (99.0, 2.0), (99.0, 3.0), (99.0, 4.0), (99.0, 5.0), (99.0, 6.0), (99.0, 7.0), (99.0, 8.0), (99.0, 9.0), (99.0, 10.0), (99.0, 11.0), (99.0, 12.0), (99.0, 13.0), (99.0, 14.0), (99.0, 15.0), (99.0, 16.0), (99.0, 17.0), (99.0, 18.0), (99.0, 19.0), (99.0, 20.0), (99.0, 21.0), (99.0, 22.0), (99.0, 23.0), (99.0, 24.0), (99.0, 25.0), (99.0, 26.0), (99.0, 27.0), (99.0, 28.0), (99.0, 29.0), (99.0, 30.0), (99.0, 31.0), (99.0, 32.0), (99.0, 33.0), (99.0, 34.0), (99.0, 35.0), (99.0, 36.0), (99.0, 37.0), (99.0, 38.0), (99.0, 39.0), (99.0, 40.0), (99.0, 41.0), (99.0, 42.0), (99.0, 43.0), (99.0, 44.0), (99.0, 45.0), (99.0, 46.0), (99.0, 47.0), (99.0, 48.0), (99.0, 49.0), (99.0, 50.0), (99.0, 51.0), (99.0, 52.0), (99.0, 53.0), (99.0, 54.0), (99.0, 55.0), (99.0, 56.0), (99.0, 57.0), (99.0, 58.0), (99.0, 59.0), (99.0, 60.0), (99.0, 61.0), (99.0, 62.0), (99.0, 63.0), (99.0, 64.0), (99.0, 65.0), (99.0, 66.0), (99.0, 67.0), (99.0, 68.0), (99.0, 69.0), (99.0, 70.0), (99.0, 71.0), (99.0, 72.0), (99.0, 73.0), (99.0, 74.0), (99.0, 75.0), (99.0, 76.0), (99.0, 77.0), (99.0, 78.0), (99.0, 79.0), (99.0, 80.0), (99.0, 81.0), (99.0, 82.0), (99.0, 83.0), (99.0, 84.0), (99.0, 85.0), (99.0, 86.0), (99.0, 87.0), (99.0, 88.0), (99.0, 89.0), (99.0, 90.0), (99.0, 91.0), (99.0, 92.0), (99.0, 93.0), (99.0, 94.0), (99.0, 95.0), (99.0, 96.0), (99.0, 97.0), (99.0, 98.0), (99.0, 99.0), (98.0, 0.0), (98.0, 80.0), (98.0, 81.0), (98.0, 82.0), (98.0, 83.0), (98.0, 84.0), (98.0, 85.0), (98.0, 99.0), (97.0, 0.0), (97.0, 79.0), (97.0, 80.0), (97.0, 81.0), (97.0, 82.0), (97.0, 83.0), (97.0, 84.0), (97.0, 99.0), (96.0, 0.0), (96.0, 78.0), (96.0, 79.0), (96.0, 80.0), (96.0, 81.0), (96.0, 82.0), (96.0, 83.0), (96.0, 99.0), (95.0, 0.0), (95.0, 77.0), (95.0, 78.0), (95.0, 79.0), (95.0, 80.0), (95.0, 81.0), (95.0, 82.0), (95.0, 99.0), (94.0, 0.0), (94.0, 76.0), (94.0, 77.0), (94.0, 78.0), (94.0, 79.0), (94.0, 80.0), (94.0, 81.0), (94.0, 99.0), (93.0, 0.0), (93.0, 75.0), (93.0, 76.0), (93.0, 77.0), (93.0, 78.0), (93.0, 79.0), (93.0, 80.0), (93.0, 99.0), (92.0, 0.0), (92.0, 74.0), (92.0, 75.0), (92.0, 76.0), (92.0, 77.0), (92.0, 78.0), (92.0, 79.0), (92.0, 99.0), (91.0, 0.0), (91.0, 73.0), (91.0, 74.0), (91.0, 75.0), (91.0, 76.0), (91.0, 77.0), (91.0, 78.0), (91.0, 99.0), (90.0, 0.0), (90.0, 72.0), (90.0, 73.0), (90.0, 74.0), (90.0, 75.0), (90.0, 76.0), (90.0, 77.0), (90.0, 99.0), (89.0, 0.0), (89.0, 10.0), (89.0, 11.0), (89.0, 12.0), (89.0, 13.0), (89.0, 14.0), (89.0, 15.0), (89.0, 16.0), (89.0, 17.0), (89.0, 18.0), (89.0, 19.0), (89.0, 25.0), (89.0, 26.0), (89.0, 27.0), (89.0, 28.0), (89.0, 29.0), (89.0, 30.0), (89.0, 37.0), (89.0, 50.0), (89.0, 51.0), (89.0, 52.0), (89.0, 53.0), (89.0, 54.0), (89.0, 71.0), (89.0, 72.0), (89.0, 73.0), (89.0, 74.0), (89.0, 75.0), (89.0, 76.0), (89.0, 99.0), (88.0, 0.0), (88.0, 10.0), (88.0, 11.0), (88.0, 12.0), (88.0, 13.0), (88.0, 14.0), (88.0, 15.0), (88.0, 16.0), (88.0, 17.0), (88.0, 18.0), (88.0, 19.0), (88.0, 25.0), (88.0, 26.0), (88.0, 27.0), (88.0, 28.0), (88.0, 29.0), (88.0, 30.0), (88.0, 37.0), (88.0, 50.0), (88.0, 51.0), (88.0, 52.0), (88.0, 53.0), (88.0, 54.0), (88.0, 70.0), (88.0, 71.0), (88.0, 72.0), (88.0, 73.0), (88.0, 74.0), (88.0, 75.0), (88.0, 99.0), (87.0, 0.0), (87.0, 10.0), (87.0, 11.0), (87.0, 12.0), (87.0, 13.0), (87.0, 14.0), (87.0, 15.0), (87.0, 16.0), (87.0, 17.0), (87.0, 18.0), (87.0, 19.0), (87.0, 25.0), (87.0, 26.0), (87.0, 27.0), (87.0, 28.0), (87.0, 29.0), (87.0, 30.0), (87.0, 37.0), (87.0, 50.0), (87.0, 51.0), (87.0, 52.0), (87.0, 53.0), (87.0, 54.0), (87.0, 70.0), (87.0, 71.0), (87.0, 72.0), (87.0, 73.0), (87.0, 74.0), (87.0, 99.0), (86.0, 0.0), (86.0, 10.0), (86.0, 11.0), (86.0, 12.0), (86.0, 13.0), (86.0, 14.0), (86.0, 15.0), (86.0, 16.0), (86.0, 17.0), (86.0, 18.0), (86.0, 19.0), (86.0, 25.0), (86.0, 26.0), (86.0, 27.0), (86.0, 28.0), (86.0, 29.0), (86.0, 30.0), (86.0, 37.0), (86.0, 50.0), (86.0, 51.0), (86.0, 52.0), (86.0, 53.0), (86.0, 54.0), (86.0, 70.0), (86.0, 71.0), (86.0, 72.0), (86.0, 73.0), (86.0, 99.0), (85.0, 0.0), (85.0, 10.0), (85.0, 11.0), (85.0, 12.0), (85.0, 13.0), (85.0, 14.0), (85.0, 15.0), (85.0, 16.0), (85.0, 17.0), (85.0, 18.0), (85.0, 19.0), (85.0, 25.0), (85.0, 26.0), (85.0, 27.0), (85.0, 28.0), (85.0, 29.0), (85.0, 30.0), (85.0, 37.0), (85.0, 50.0), (85.0, 51.0), (85.0, 52.0), (85.0, 53.0), (85.0, 54.0), (85.0, 70.0), (85.0, 71.0), (85.0, 72.0), (85.0, 99.0), (84.0, 0.0), (84.0, 10.0), (84.0, 11.0), (84.0, 12.0), (84.0, 13.0), (84.0, 14.0), (84.0, 15.0), (84.0, 16.0), (84.0, 17.0), (84.0, 18.0), (84.0, 19.0), (84.0, 25.0), (84.0, 26.0), (84.0, 27.0), (84.0, 28.0), (84.0, 29.0), (84.0, 30.0), (84.0, 37.0), (84.0, 70.0), (84.0, 71.0), (84.0, 99.0), (83.0, 0.0), (83.0, 10.0), (83.0, 11.0), (83.0, 12.0), (83.0, 13.0), (83.0, 14.0), (83.0, 15.0), (83.0, 16.0), (83.0, 17.0), (83.0, 18.0), (83.0, 19.0), (83.0, 25.0), (83.0, 26.0), (83.0, 27.0), (83.0, 28.0), (83.0, 29.0), (83.0, 30.0), (83.0, 37.0), (83.0, 99.0), (82.0, 0.0), (82.0, 10.0), (82.0, 11.0), (82.0, 12.0), (82.0, 13.0), (82.0, 14.0), (82.0, 15.0), (82.0, 16.0), (82.0, 17.0), (82.0, 18.0), (82.0, 19.0), (82.0, 25.0), (82.0, 26.0), (82.0, 27.0), (82.0, 28.0), (82.0, 29.0), (82.0, 30.0), (82.0, 37.0), (82.0, 99.0), (81.0, 0.0), (81.0, 10.0), (81.0, 11.0), (81.0, 12.0), (81.0, 13.0), (81.0, 14.0), (81.0, 15.0), (81.0, 16.0), (81.0, 17.0), (81.0, 18.0), (81.0, 19.0), (81.0, 25.0), (81.0, 26.0), (81.0, 27.0), (81.0, 28.0), (81.0, 29.0), (81.0, 30.0), (81.0, 37.0), (81.0, 99.0), (80.0, 0.0), (80.0, 10.0), (80.0, 11.0), (80.0, 12.0), (80.0, 13.0), (80.0, 14.0), (80.0, 15.0), (80.0, 16.0), (80.0, 17.0), (80.0, 18.0), (80.0, 19.0), (80.0, 25.0), (80.0, 26.0), (80.0, 27.0), (80.0, 28.0), (80.0, 29.0), (80.0, 30.0), (80.0, 37.0), (80.0, 99.0), (79.0, 0.0), (79.0, 10.0), (79.0, 11.0), (79.0, 12.0), (79.0, 13.0), (79.0, 14.0), (79.0, 15.0), (79.0, 16.0), (79.0, 17.0), (79.0, 18.0), (79.0, 19.0), (79.0, 25.0), (79.0, 26.0), (79.0, 27.0), (79.0, 28.0), (79.0, 29.0), (79.0, 30.0), (79.0, 35.0), (79.0, 36.0), (79.0, 37.0), (79.0, 38.0), (79.0, 39.0), (79.0, 80.0), (79.0, 81.0), (79.0, 82.0), (79.0, 83.0), (79.0, 84.0), (79.0, 85.0), (79.0, 86.0), (79.0, 87.0), (79.0, 88.0), (79.0, 89.0), (79.0, 99.0), (78.0, 0.0), (78.0, 11.0), (78.0, 12.0), (78.0, 13.0), (78.0, 14.0), (78.0, 15.0), (78.0, 16.0), (78.0, 17.0), (78.0, 18.0), (78.0, 19.0), (78.0, 25.0), (78.0, 26.0), (78.0, 27.0), (78.0, 28.0), (78.0, 29.0), (78.0, 30.0), (78.0, 35.0), (78.0, 36.0), (78.0, 37.0), (78.0, 38.0), (78.0, 39.0), (78.0, 80.0), (78.0, 81.0), (78.0, 82.0), (78.0, 83.0), (78.0, 84.0), (78.0, 85.0), (78.0, 86.0), (78.0, 87.0), (78.0, 88.0), (78.0, 89.0), (78.0, 99.0), (77.0, 0.0), (77.0, 12.0), (77.0, 13.0), (77.0, 14.0), (77.0, 15.0), (77.0, 16.0), (77.0, 17.0), (77.0, 18.0), (77.0, 19.0), (77.0, 25.0), (77.0, 26.0), (77.0, 27.0), (77.0, 28.0), (77.0, 29.0), (77.0, 30.0), (77.0, 35.0), (77.0, 36.0), (77.0, 37.0), (77.0, 38.0), (77.0, 39.0), (77.0, 80.0), (77.0, 81.0), (77.0, 82.0), (77.0, 83.0), (77.0, 84.0), (77.0, 85.0), (77.0, 86.0), (77.0, 87.0), (77.0, 88.0), (77.0, 89.0), (77.0, 99.0), (76.0, 0.0), (76.0, 13.0), (76.0, 14.0), (76.0, 15.0), (76.0, 16.0), (76.0, 17.0), (76.0, 18.0), (76.0, 19.0), (76.0, 25.0), (76.0, 26.0), (76.0, 27.0), (76.0, 28.0), (76.0, 29.0), (76.0, 30.0), (76.0, 35.0), (76.0, 36.0), (76.0, 37.0), (76.0, 38.0), (76.0, 39.0), (76.0, 80.0), (76.0, 81.0), (76.0, 82.0), (76.0, 83.0), (76.0, 84.0), (76.0, 85.0), (76.0, 86.0), (76.0, 87.0), (76.0, 88.0), (76.0, 89.0), (76.0, 99.0), (75.0, 0.0), (75.0, 13.0), (75.0, 14.0), (75.0, 15.0), (75.0, 16.0), (75.0, 17.0), (75.0, 18.0), (75.0, 19.0), (75.0, 25.0), (75.0, 26.0), (75.0, 27.0), (75.0, 28.0), (75.0, 29.0), (75.0, 30.0), (75.0, 35.0), (75.0, 36.0), (75.0, 37.0), (75.0, 38.0), (75.0, 39.0), (75.0, 55.0), (75.0, 56.0), (75.0, 57.0), (75.0, 58.0), (75.0, 59.0), (75.0, 80.0), (75.0, 81.0), (75.0, 82.0), (75.0, 83.0), (75.0, 84.0), (75.0, 85.0), (75.0, 86.0), (75.0, 87.0), (75.0, 88.0), (75.0, 89.0), (75.0, 99.0), (74.0, 0.0), (74.0, 13.0), (74.0, 14.0), (74.0, 15.0), (74.0, 16.0), (74.0, 55.0), (74.0, 56.0), (74.0, 57.0), (74.0, 58.0), (74.0, 59.0), (74.0, 80.0), (74.0, 81.0), (74.0, 82.0), (74.0, 83.0), (74.0, 84.0), (74.0, 85.0), (74.0, 86.0), (74.0, 87.0), (74.0, 88.0), (74.0, 89.0), (74.0, 99.0), (73.0, 0.0), (73.0, 13.0), (73.0, 14.0), (73.0, 15.0), (73.0, 16.0), (73.0, 55.0), (73.0, 56.0), (73.0, 57.0), (73.0, 58.0), (73.0, 59.0), (73.0, 80.0), (73.0, 81.0), (73.0, 82.0), (73.0, 83.0), (73.0, 84.0), (73.0, 85.0), (73.0, 86.0), (73.0, 87.0), (73.0, 88.0), (73.0, 89.0), (73.0, 99.0), (72.0, 0.0), (72.0, 13.0), (72.0, 14.0), (72.0, 15.0), (72.0, 16.0), (72.0, 55.0), (72.0, 56.0), (72.0, 57.0), (72.0, 58.0), (72.0, 59.0), (72.0, 80.0), (72.0, 81.0), (72.0, 82.0), (72.0, 83.0), (72.0, 84.0), (72.0, 85.0), (72.0, 86.0), (72.0, 87.0), (72.0, 88.0), (72.0, 89.0), (72.0, 99.0), (71.0, 0.0), (71.0, 13.0), (71.0, 14.0), (71.0, 15.0), (71.0, 16.0), (71.0, 55.0), (71.0, 56.0), (71.0, 57.0), (71.0, 58.0), (71.0, 59.0), (71.0, 80.0), (71.0, 81.0), (71.0, 82.0), (71.0, 83.0), (71.0, 84.0), (71.0, 85.0), (71.0, 86.0), (71.0, 87.0), (71.0, 88.0), (71.0, 89.0), (71.0, 99.0), (70.0, 0.0), (70.0, 13.0), (70.0, 14.0), (70.0, 15.0), (70.0, 16.0), (70.0, 80.0), (70.0, 81.0), (70.0, 82.0), (70.0, 83.0), (70.0, 84.0), (70.0, 85.0), (70.0, 86.0), (70.0, 87.0), (70.0, 88.0), (70.0, 89.0), (70.0, 99.0), (69.0, 0.0), (69.0, 13.0), (69.0, 14.0), (69.0, 15.0), (69.0, 16.0), (69.0, 31.0), (69.0, 32.0), (69.0, 33.0), (69.0, 34.0), (69.0, 35.0), (69.0, 36.0), (69.0, 37.0), (69.0, 38.0), (69.0, 39.0), (69.0, 40.0), (69.0, 41.0), (69.0, 99.0), (68.0, 0.0), (68.0, 13.0), (68.0, 14.0), (68.0, 15.0), (68.0, 16.0), (68.0, 31.0), (68.0, 32.0), (68.0, 33.0), (68.0, 34.0), (68.0, 35.0), (68.0, 36.0), (68.0, 37.0), (68.0, 38.0), (68.0, 39.0), (68.0, 40.0), (68.0, 41.0), (68.0, 99.0), (67.0, 0.0), (67.0, 13.0), (67.0, 14.0), (67.0, 15.0), (67.0, 16.0), (67.0, 31.0), (67.0, 32.0), (67.0, 99.0), (66.0, 0.0), (66.0, 13.0), (66.0, 14.0), (66.0, 15.0), (66.0, 16.0), (66.0, 24.0), (66.0, 25.0), (66.0, 31.0), (66.0, 32.0), (66.0, 77.0), (66.0, 78.0), (66.0, 79.0), (66.0, 80.0), (66.0, 81.0), (66.0, 82.0), (66.0, 83.0), (66.0, 84.0), (66.0, 92.0), (66.0, 93.0), (66.0, 94.0), (66.0, 95.0), (66.0, 99.0), (65.0, 0.0), (65.0, 13.0), (65.0, 14.0), (65.0, 15.0), (65.0, 16.0), (65.0, 24.0), (65.0, 25.0), (65.0, 31.0), (65.0, 32.0), (65.0, 77.0), (65.0, 78.0), (65.0, 79.0), (65.0, 80.0), (65.0, 81.0), (65.0, 82.0), (65.0, 83.0), (65.0, 84.0), (65.0, 91.0), (65.0, 92.0), (65.0, 93.0), (65.0, 94.0), (65.0, 95.0), (65.0, 99.0), (64.0, 0.0), (64.0, 13.0), (64.0, 14.0), (64.0, 15.0), (64.0, 16.0), (64.0, 24.0), (64.0, 25.0), (64.0, 31.0), (64.0, 32.0), (64.0, 78.0), (64.0, 79.0), (64.0, 80.0), (64.0, 81.0), (64.0, 82.0), (64.0, 83.0), (64.0, 84.0), (64.0, 91.0), (64.0, 92.0), (64.0, 93.0), (64.0, 94.0), (64.0, 99.0), (63.0, 0.0), (63.0, 13.0), (63.0, 14.0), (63.0, 15.0), (63.0, 16.0), (63.0, 24.0), (63.0, 25.0), (63.0, 31.0), (63.0, 32.0), (63.0, 78.0), (63.0, 79.0), (63.0, 80.0), (63.0, 81.0), (63.0, 82.0), (63.0, 83.0), (63.0, 84.0), (63.0, 91.0), (63.0, 92.0), (63.0, 93.0), (63.0, 94.0), (63.0, 99.0), (62.0, 0.0), (62.0, 13.0), (62.0, 14.0), (62.0, 15.0), (62.0, 16.0), (62.0, 24.0), (62.0, 25.0), (62.0, 31.0), (62.0, 32.0), (62.0, 78.0), (62.0, 79.0), (62.0, 80.0), (62.0, 81.0), (62.0, 82.0), (62.0, 83.0), (62.0, 92.0), (62.0, 93.0), (62.0, 99.0), (61.0, 0.0), (61.0, 13.0), (61.0, 14.0), (61.0, 15.0), (61.0, 16.0), (61.0, 24.0), (61.0, 25.0), (61.0, 31.0), (61.0, 32.0), (61.0, 69.0), (61.0, 70.0), (61.0, 71.0), (61.0, 72.0), (61.0, 79.0), (61.0, 80.0), (61.0, 81.0), (61.0, 82.0), (61.0, 99.0), (60.0, 0.0), (60.0, 13.0), (60.0, 14.0), (60.0, 15.0), (60.0, 16.0), (60.0, 24.0), (60.0, 25.0), (60.0, 31.0), (60.0, 32.0), (60.0, 39.0), (60.0, 40.0), (60.0, 41.0), (60.0, 42.0), (60.0, 43.0), (60.0, 44.0), (60.0, 45.0), (60.0, 46.0), (60.0, 47.0), (60.0, 48.0), (60.0, 49.0), (60.0, 50.0), (60.0, 51.0), (60.0, 52.0), (60.0, 53.0), (60.0, 54.0), (60.0, 55.0), (60.0, 68.0), (60.0, 69.0), (60.0, 70.0), (60.0, 71.0), (60.0, 72.0), (60.0, 73.0), (60.0, 79.0), (60.0, 80.0), (60.0, 98.0), (60.0, 99.0), (59.0, 0.0), (59.0, 13.0), (59.0, 14.0), (59.0, 15.0), (59.0, 16.0), (59.0, 24.0), (59.0, 25.0), (59.0, 31.0), (59.0, 32.0), (59.0, 39.0), (59.0, 40.0), (59.0, 41.0), (59.0, 42.0), (59.0, 43.0), (59.0, 44.0), (59.0, 45.0), (59.0, 46.0), (59.0, 47.0), (59.0, 48.0), (59.0, 49.0), (59.0, 50.0), (59.0, 51.0), (59.0, 52.0), (59.0, 53.0), (59.0, 54.0), (59.0, 55.0), (59.0, 56.0), (59.0, 67.0), (59.0, 68.0), (59.0, 69.0), (59.0, 70.0), (59.0, 71.0), (59.0, 72.0), (59.0, 73.0), (59.0, 79.0), (59.0, 89.0), (59.0, 97.0), (59.0, 98.0), (59.0, 99.0), (58.0, 0.0), (58.0, 13.0), (58.0, 14.0), (58.0, 15.0), (58.0, 16.0), (58.0, 24.0), (58.0, 25.0), (58.0, 31.0), (58.0, 32.0), (58.0, 39.0), (58.0, 40.0), (58.0, 66.0), (58.0, 67.0), (58.0, 68.0), (58.0, 69.0), (58.0, 70.0), (58.0, 71.0), (58.0, 72.0), (58.0, 73.0), (58.0, 79.0), (58.0, 89.0), (58.0, 95.0), (58.0, 96.0), (58.0, 97.0), (58.0, 98.0), (58.0, 99.0), (57.0, 0.0), (57.0, 13.0), (57.0, 14.0), (57.0, 15.0), (57.0, 16.0), (57.0, 24.0), (57.0, 25.0), (57.0, 31.0), (57.0, 32.0), (57.0, 39.0), (57.0, 40.0), (57.0, 65.0), (57.0, 66.0), (57.0, 67.0), (57.0, 68.0), (57.0, 69.0), (57.0, 70.0), (57.0, 71.0), (57.0, 72.0), (57.0, 88.0), (57.0, 89.0), (57.0, 95.0), (57.0, 96.0), (57.0, 97.0), (57.0, 98.0), (57.0, 99.0), (56.0, 0.0), (56.0, 13.0), (56.0, 14.0), (56.0, 15.0), (56.0, 16.0), (56.0, 24.0), (56.0, 25.0), (56.0, 31.0), (56.0, 32.0), (56.0, 39.0), (56.0, 40.0), (56.0, 64.0), (56.0, 65.0), (56.0, 66.0), (56.0, 67.0), (56.0, 68.0), (56.0, 69.0), (56.0, 70.0), (56.0, 71.0), (56.0, 87.0), (56.0, 88.0), (56.0, 89.0), (56.0, 95.0), (56.0, 96.0), (56.0, 97.0), (56.0, 98.0), (56.0, 99.0), (55.0, 0.0), (55.0, 13.0), (55.0, 14.0), (55.0, 15.0), (55.0, 16.0), (55.0, 24.0), (55.0, 25.0), (55.0, 39.0), (55.0, 40.0), (55.0, 64.0), (55.0, 65.0), (55.0, 66.0), (55.0, 67.0), (55.0, 68.0), (55.0, 69.0), (55.0, 70.0), (55.0, 71.0), (55.0, 86.0), (55.0, 87.0), (55.0, 88.0), (55.0, 89.0), (55.0, 96.0), (55.0, 97.0), (55.0, 98.0), (55.0, 99.0), (54.0, 0.0), (54.0, 13.0), (54.0, 14.0), (54.0, 15.0), (54.0, 16.0), (54.0, 24.0), (54.0, 25.0), (54.0, 39.0), (54.0, 40.0), (54.0, 63.0), (54.0, 64.0), (54.0, 65.0), (54.0, 66.0), (54.0, 67.0), (54.0, 68.0), (54.0, 69.0), (54.0, 70.0), (54.0, 85.0), (54.0, 86.0), (54.0, 87.0), (54.0, 88.0), (54.0, 97.0), (54.0, 98.0), (54.0, 99.0), (53.0, 0.0), (53.0, 13.0), (53.0, 14.0), (53.0, 15.0), (53.0, 16.0), (53.0, 24.0), (53.0, 25.0), (53.0, 39.0), (53.0, 40.0), (53.0, 64.0), (53.0, 65.0), (53.0, 66.0), (53.0, 67.0), (53.0, 68.0), (53.0, 69.0), (53.0, 83.0), (53.0, 84.0), (53.0, 85.0), (53.0, 86.0), (53.0, 87.0), (53.0, 98.0), (53.0, 99.0), (52.0, 0.0), (52.0, 13.0), (52.0, 14.0), (52.0, 15.0), (52.0, 16.0), (52.0, 24.0), (52.0, 25.0), (52.0, 39.0), (52.0, 40.0), (52.0, 64.0), (52.0, 65.0), (52.0, 66.0), (52.0, 67.0), (52.0, 82.0), (52.0, 83.0), (52.0, 84.0), (52.0, 85.0), (52.0, 99.0), (51.0, 0.0), (51.0, 13.0), (51.0, 14.0), (51.0, 15.0), (51.0, 16.0), (51.0, 24.0), (51.0, 25.0), (51.0, 26.0), (51.0, 27.0), (51.0, 28.0), (51.0, 29.0), (51.0, 30.0), (51.0, 31.0), (51.0, 32.0), (51.0, 33.0), (51.0, 34.0), (51.0, 35.0), (51.0, 36.0), (51.0, 37.0), (51.0, 38.0), (51.0, 39.0), (51.0, 40.0), (51.0, 58.0), (51.0, 59.0), (51.0, 77.0), (51.0, 78.0), (51.0, 79.0), (51.0, 80.0), (51.0, 81.0), (51.0, 82.0), (51.0, 83.0), (51.0, 84.0), (51.0, 99.0), (50.0, 0.0), (50.0, 13.0), (50.0, 14.0), (50.0, 15.0), (50.0, 16.0), (50.0, 24.0), (50.0, 25.0), (50.0, 26.0), (50.0, 27.0), (50.0, 28.0), (50.0, 29.0), (50.0, 30.0), (50.0, 31.0), (50.0, 32.0), (50.0, 33.0), (50.0, 34.0), (50.0, 35.0), (50.0, 36.0), (50.0, 37.0), (50.0, 38.0), (50.0, 39.0), (50.0, 40.0), (50.0, 49.0), (50.0, 50.0), (50.0, 51.0), (50.0, 52.0), (50.0, 53.0), (50.0, 54.0), (50.0, 55.0), (50.0, 56.0), (50.0, 57.0), (50.0, 58.0), (50.0, 59.0), (50.0, 76.0), (50.0, 77.0), (50.0, 78.0), (50.0, 79.0), (50.0, 80.0), (50.0, 81.0), (50.0, 82.0), (50.0, 92.0), (50.0, 93.0), (50.0, 99.0), (49.0, 0.0), (49.0, 13.0), (49.0, 14.0), (49.0, 15.0), (49.0, 16.0), (49.0, 39.0), (49.0, 40.0), (49.0, 49.0), (49.0, 50.0), (49.0, 51.0), (49.0, 52.0), (49.0, 53.0), (49.0, 54.0), (49.0, 55.0), (49.0, 56.0), (49.0, 57.0), (49.0, 58.0), (49.0, 91.0), (49.0, 92.0), (49.0, 93.0), (49.0, 94.0), (49.0, 99.0), (48.0, 0.0), (48.0, 13.0), (48.0, 14.0), (48.0, 15.0), (48.0, 16.0), (48.0, 39.0), (48.0, 40.0), (48.0, 49.0), (48.0, 50.0), (48.0, 51.0), (48.0, 52.0), (48.0, 53.0), (48.0, 54.0), (48.0, 55.0), (48.0, 56.0), (48.0, 57.0), (48.0, 90.0), (48.0, 91.0), (48.0, 92.0), (48.0, 93.0), (48.0, 94.0), (48.0, 95.0), (48.0, 99.0), (47.0, 0.0), (47.0, 13.0), (47.0, 14.0), (47.0, 15.0), (47.0, 16.0), (47.0, 39.0), (47.0, 40.0), (47.0, 49.0), (47.0, 50.0), (47.0, 51.0), (47.0, 52.0), (47.0, 53.0), (47.0, 54.0), (47.0, 63.0), (47.0, 64.0), (47.0, 89.0), (47.0, 90.0), (47.0, 91.0), (47.0, 92.0), (47.0, 93.0), (47.0, 94.0), (47.0, 95.0), (47.0, 99.0), (46.0, 0.0), (46.0, 13.0), (46.0, 14.0), (46.0, 15.0), (46.0, 16.0), (46.0, 39.0), (46.0, 40.0), (46.0, 49.0), (46.0, 50.0), (46.0, 51.0), (46.0, 52.0), (46.0, 53.0), (46.0, 62.0), (46.0, 63.0), (46.0, 89.0), (46.0, 90.0), (46.0, 91.0), (46.0, 92.0), (46.0, 93.0), (46.0, 94.0), (46.0, 95.0), (46.0, 99.0), (45.0, 0.0), (45.0, 14.0), (45.0, 15.0), (45.0, 16.0), (45.0, 39.0), (45.0, 40.0), (45.0, 49.0), (45.0, 50.0), (45.0, 51.0), (45.0, 52.0), (45.0, 61.0), (45.0, 62.0), (45.0, 71.0), (45.0, 72.0), (45.0, 73.0), (45.0, 74.0), (45.0, 75.0), (45.0, 76.0), (45.0, 77.0), (45.0, 78.0), (45.0, 79.0), (45.0, 80.0), (45.0, 81.0), (45.0, 82.0), (45.0, 90.0), (45.0, 91.0), (45.0, 92.0), (45.0, 93.0), (45.0, 94.0), (45.0, 95.0), (45.0, 99.0), (44.0, 0.0), (44.0, 15.0), (44.0, 16.0), (44.0, 17.0), (44.0, 18.0), (44.0, 19.0), (44.0, 20.0), (44.0, 21.0), (44.0, 22.0), (44.0, 23.0), (44.0, 24.0), (44.0, 25.0), (44.0, 26.0), (44.0, 27.0), (44.0, 28.0), (44.0, 29.0), (44.0, 30.0), (44.0, 31.0), (44.0, 32.0), (44.0, 33.0), (44.0, 39.0), (44.0, 40.0), (44.0, 49.0), (44.0, 50.0), (44.0, 51.0), (44.0, 59.0), (44.0, 60.0), (44.0, 61.0), (44.0, 71.0), (44.0, 72.0), (44.0, 73.0), (44.0, 74.0), (44.0, 75.0), (44.0, 76.0), (44.0, 77.0), (44.0, 78.0), (44.0, 79.0), (44.0, 80.0), (44.0, 81.0), (44.0, 82.0), (44.0, 83.0), (44.0, 84.0), (44.0, 91.0), (44.0, 92.0), (44.0, 93.0), (44.0, 94.0), (44.0, 99.0), (43.0, 0.0), (43.0, 16.0), (43.0, 17.0), (43.0, 18.0), (43.0, 19.0), (43.0, 20.0), (43.0, 21.0), (43.0, 22.0), (43.0, 23.0), (43.0, 24.0), (43.0, 25.0), (43.0, 26.0), (43.0, 27.0), (43.0, 28.0), (43.0, 29.0), (43.0, 30.0), (43.0, 31.0), (43.0, 32.0), (43.0, 33.0), (43.0, 39.0), (43.0, 40.0), (43.0, 49.0), (43.0, 50.0), (43.0, 58.0), (43.0, 59.0), (43.0, 60.0), (43.0, 81.0), (43.0, 82.0), (43.0, 83.0), (43.0, 84.0), (43.0, 85.0), (43.0, 93.0), (43.0, 99.0), (42.0, 0.0), (42.0, 17.0), (42.0, 18.0), (42.0, 19.0), (42.0, 20.0), (42.0, 21.0), (42.0, 22.0), (42.0, 23.0), (42.0, 24.0), (42.0, 25.0), (42.0, 26.0), (42.0, 32.0), (42.0, 33.0), (42.0, 39.0), (42.0, 40.0), (42.0, 57.0), (42.0, 58.0), (42.0, 84.0), (42.0, 85.0), (42.0, 86.0), (42.0, 99.0), (41.0, 0.0), (41.0, 18.0), (41.0, 19.0), (41.0, 20.0), (41.0, 21.0), (41.0, 22.0), (41.0, 23.0), (41.0, 24.0), (41.0, 25.0), (41.0, 26.0), (41.0, 32.0), (41.0, 33.0), (41.0, 39.0), (41.0, 40.0), (41.0, 56.0), (41.0, 57.0), (41.0, 64.0), (41.0, 65.0), (41.0, 85.0), (41.0, 86.0), (41.0, 87.0), (41.0, 99.0), (40.0, 0.0), (40.0, 8.0), (40.0, 19.0), (40.0, 20.0), (40.0, 21.0), (40.0, 22.0), (40.0, 23.0), (40.0, 24.0), (40.0, 25.0), (40.0, 26.0), (40.0, 32.0), (40.0, 33.0), (40.0, 39.0), (40.0, 40.0), (40.0, 64.0), (40.0, 65.0), (40.0, 85.0), (40.0, 86.0), (40.0, 87.0), (40.0, 88.0), (40.0, 98.0), (40.0, 99.0), (39.0, 0.0), (39.0, 8.0), (39.0, 9.0), (39.0, 20.0), (39.0, 21.0), (39.0, 22.0), (39.0, 23.0), (39.0, 24.0), (39.0, 25.0), (39.0, 26.0), (39.0, 32.0), (39.0, 33.0), (39.0, 39.0), (39.0, 40.0), (39.0, 64.0), (39.0, 65.0), (39.0, 66.0), (39.0, 86.0), (39.0, 87.0), (39.0, 88.0), (39.0, 97.0), (39.0, 98.0), (39.0, 99.0), (38.0, 0.0), (38.0, 8.0), (38.0, 9.0), (38.0, 10.0), (38.0, 20.0), (38.0, 21.0), (38.0, 22.0), (38.0, 23.0), (38.0, 24.0), (38.0, 25.0), (38.0, 26.0), (38.0, 32.0), (38.0, 33.0), (38.0, 39.0), (38.0, 40.0), (38.0, 64.0), (38.0, 65.0), (38.0, 66.0), (38.0, 87.0), (38.0, 88.0), (38.0, 96.0), (38.0, 97.0), (38.0, 98.0), (38.0, 99.0), (37.0, 0.0), (37.0, 8.0), (37.0, 9.0), (37.0, 10.0), (37.0, 11.0), (37.0, 20.0), (37.0, 21.0), (37.0, 22.0), (37.0, 23.0), (37.0, 24.0), (37.0, 25.0), (37.0, 26.0), (37.0, 32.0), (37.0, 33.0), (37.0, 39.0), (37.0, 40.0), (37.0, 65.0), (37.0, 66.0), (37.0, 67.0), (37.0, 68.0), (37.0, 69.0), (37.0, 70.0), (37.0, 71.0), (37.0, 72.0), (37.0, 73.0), (37.0, 74.0), (37.0, 87.0), (37.0, 88.0), (37.0, 96.0), (37.0, 97.0), (37.0, 98.0), (37.0, 99.0), (36.0, 0.0), (36.0, 8.0), (36.0, 9.0), (36.0, 10.0), (36.0, 11.0), (36.0, 12.0), (36.0, 20.0), (36.0, 21.0), (36.0, 22.0), (36.0, 23.0), (36.0, 24.0), (36.0, 25.0), (36.0, 26.0), (36.0, 32.0), (36.0, 33.0), (36.0, 39.0), (36.0, 40.0), (36.0, 49.0), (36.0, 50.0), (36.0, 51.0), (36.0, 52.0), (36.0, 53.0), (36.0, 54.0), (36.0, 55.0), (36.0, 66.0), (36.0, 67.0), (36.0, 68.0), (36.0, 69.0), (36.0, 70.0), (36.0, 71.0), (36.0, 72.0), (36.0, 73.0), (36.0, 74.0), (36.0, 75.0), (36.0, 76.0), (36.0, 88.0), (36.0, 95.0), (36.0, 96.0), (36.0, 97.0), (36.0, 98.0), (36.0, 99.0), (35.0, 0.0), (35.0, 8.0), (35.0, 9.0), (35.0, 10.0), (35.0, 11.0), (35.0, 12.0), (35.0, 20.0), (35.0, 21.0), (35.0, 22.0), (35.0, 23.0), (35.0, 24.0), (35.0, 25.0), (35.0, 26.0), (35.0, 32.0), (35.0, 33.0), (35.0, 39.0), (35.0, 40.0), (35.0, 49.0), (35.0, 50.0), (35.0, 51.0), (35.0, 52.0), (35.0, 53.0), (35.0, 54.0), (35.0, 55.0), (35.0, 56.0), (35.0, 73.0), (35.0, 74.0), (35.0, 75.0), (35.0, 76.0), (35.0, 77.0), (35.0, 78.0), (35.0, 88.0), (35.0, 95.0), (35.0, 96.0), (35.0, 97.0), (35.0, 98.0), (35.0, 99.0), (34.0, 0.0), (34.0, 8.0), (34.0, 9.0), (34.0, 10.0), (34.0, 11.0), (34.0, 12.0), (34.0, 20.0), (34.0, 21.0), (34.0, 22.0), (34.0, 23.0), (34.0, 24.0), (34.0, 25.0), (34.0, 26.0), (34.0, 32.0), (34.0, 33.0), (34.0, 39.0), (34.0, 40.0), (34.0, 49.0), (34.0, 50.0), (34.0, 51.0), (34.0, 52.0), (34.0, 53.0), (34.0, 54.0), (34.0, 55.0), (34.0, 56.0), (34.0, 57.0), (34.0, 75.0), (34.0, 76.0), (34.0, 77.0), (34.0, 78.0), (34.0, 79.0), (34.0, 95.0), (34.0, 96.0), (34.0, 97.0), (34.0, 98.0), (34.0, 99.0), (33.0, 0.0), (33.0, 8.0), (33.0, 9.0), (33.0, 10.0), (33.0, 11.0), (33.0, 12.0), (33.0, 20.0), (33.0, 21.0), (33.0, 22.0), (33.0, 23.0), (33.0, 24.0), (33.0, 25.0), (33.0, 26.0), (33.0, 32.0), (33.0, 33.0), (33.0, 39.0), (33.0, 40.0), (33.0, 49.0), (33.0, 50.0), (33.0, 51.0), (33.0, 52.0), (33.0, 53.0), (33.0, 54.0), (33.0, 55.0), (33.0, 56.0), (33.0, 57.0), (33.0, 58.0), (33.0, 77.0), (33.0, 78.0), (33.0, 79.0), (33.0, 80.0), (33.0, 95.0), (33.0, 96.0), (33.0, 97.0), (33.0, 98.0), (33.0, 99.0), (32.0, 0.0), (32.0, 8.0), (32.0, 9.0), (32.0, 10.0), (32.0, 11.0), (32.0, 12.0), (32.0, 20.0), (32.0, 21.0), (32.0, 22.0), (32.0, 23.0), (32.0, 24.0), (32.0, 25.0), (32.0, 26.0), (32.0, 32.0), (32.0, 33.0), (32.0, 39.0), (32.0, 40.0), (32.0, 49.0), (32.0, 50.0), (32.0, 51.0), (32.0, 52.0), (32.0, 53.0), (32.0, 54.0), (32.0, 55.0), (32.0, 56.0), (32.0, 57.0), (32.0, 58.0), (32.0, 59.0), (32.0, 78.0), (32.0, 79.0), (32.0, 80.0), (32.0, 81.0), (32.0, 96.0), (32.0, 97.0), (32.0, 98.0), (32.0, 99.0), (31.0, 0.0), (31.0, 8.0), (31.0, 9.0), (31.0, 10.0), (31.0, 11.0), (31.0, 12.0), (31.0, 20.0), (31.0, 21.0), (31.0, 22.0), (31.0, 23.0), (31.0, 24.0), (31.0, 25.0), (31.0, 26.0), (31.0, 32.0), (31.0, 33.0), (31.0, 39.0), (31.0, 40.0), (31.0, 49.0), (31.0, 50.0), (31.0, 51.0), (31.0, 52.0), (31.0, 53.0), (31.0, 54.0), (31.0, 55.0), (31.0, 56.0), (31.0, 57.0), (31.0, 58.0), (31.0, 59.0), (31.0, 60.0), (31.0, 79.0), (31.0, 80.0), (31.0, 81.0), (31.0, 82.0), (31.0, 83.0), (31.0, 96.0), (31.0, 97.0), (31.0, 98.0), (31.0, 99.0), (30.0, 0.0), (30.0, 8.0), (30.0, 9.0), (30.0, 10.0), (30.0, 11.0), (30.0, 12.0), (30.0, 13.0), (30.0, 20.0), (30.0, 21.0), (30.0, 22.0), (30.0, 23.0), (30.0, 24.0), (30.0, 25.0), (30.0, 26.0), (30.0, 32.0), (30.0, 33.0), (30.0, 39.0), (30.0, 40.0), (30.0, 49.0), (30.0, 50.0), (30.0, 51.0), (30.0, 52.0), (30.0, 53.0), (30.0, 54.0), (30.0, 55.0), (30.0, 56.0), (30.0, 57.0), (30.0, 58.0), (30.0, 59.0), (30.0, 60.0), (30.0, 81.0), (30.0, 82.0), (30.0, 83.0), (30.0, 84.0), (30.0, 96.0), (30.0, 97.0), (30.0, 98.0), (30.0, 99.0), (29.0, 0.0), (29.0, 8.0), (29.0, 9.0), (29.0, 10.0), (29.0, 11.0), (29.0, 12.0), (29.0, 13.0), (29.0, 14.0), (29.0, 20.0), (29.0, 21.0), (29.0, 22.0), (29.0, 23.0), (29.0, 24.0), (29.0, 25.0), (29.0, 26.0), (29.0, 32.0), (29.0, 33.0), (29.0, 39.0), (29.0, 40.0), (29.0, 49.0), (29.0, 50.0), (29.0, 51.0), (29.0, 52.0), (29.0, 53.0), (29.0, 54.0), (29.0, 55.0), (29.0, 56.0), (29.0, 57.0), (29.0, 58.0), (29.0, 59.0), (29.0, 60.0), (29.0, 68.0), (29.0, 69.0), (29.0, 82.0), (29.0, 83.0), (29.0, 84.0), (29.0, 97.0), (29.0, 98.0), (29.0, 99.0), (28.0, 0.0), (28.0, 9.0), (28.0, 10.0), (28.0, 11.0), (28.0, 12.0), (28.0, 13.0), (28.0, 14.0), (28.0, 15.0), (28.0, 16.0), (28.0, 17.0), (28.0, 18.0), (28.0, 19.0), (28.0, 20.0), (28.0, 21.0), (28.0, 22.0), (28.0, 23.0), (28.0, 24.0), (28.0, 25.0), (28.0, 26.0), (28.0, 32.0), (28.0, 33.0), (28.0, 39.0), (28.0, 40.0), (28.0, 49.0), (28.0, 50.0), (28.0, 51.0), (28.0, 52.0), (28.0, 53.0), (28.0, 54.0), (28.0, 55.0), (28.0, 56.0), (28.0, 57.0), (28.0, 58.0), (28.0, 67.0), (28.0, 68.0), (28.0, 69.0), (28.0, 70.0), (28.0, 83.0), (28.0, 84.0), (28.0, 85.0), (28.0, 92.0), (28.0, 98.0), (28.0, 99.0), (27.0, 0.0), (27.0, 11.0), (27.0, 12.0), (27.0, 13.0), (27.0, 14.0), (27.0, 15.0), (27.0, 16.0), (27.0, 17.0), (27.0, 18.0), (27.0, 19.0), (27.0, 20.0), (27.0, 21.0), (27.0, 22.0), (27.0, 23.0), (27.0, 24.0), (27.0, 25.0), (27.0, 26.0), (27.0, 32.0), (27.0, 33.0), (27.0, 39.0), (27.0, 40.0), (27.0, 49.0), (27.0, 50.0), (27.0, 51.0), (27.0, 52.0), (27.0, 53.0), (27.0, 54.0), (27.0, 55.0), (27.0, 56.0), (27.0, 66.0), (27.0, 67.0), (27.0, 68.0), (27.0, 69.0), (27.0, 70.0), (27.0, 71.0), (27.0, 84.0), (27.0, 85.0), (27.0, 92.0), (27.0, 93.0), (27.0, 98.0), (27.0, 99.0), (26.0, 0.0), (26.0, 11.0), (26.0, 12.0), (26.0, 13.0), (26.0, 14.0), (26.0, 15.0), (26.0, 16.0), (26.0, 17.0), (26.0, 18.0), (26.0, 19.0), (26.0, 20.0), (26.0, 21.0), (26.0, 22.0), (26.0, 23.0), (26.0, 24.0), (26.0, 25.0), (26.0, 26.0), (26.0, 32.0), (26.0, 33.0), (26.0, 39.0), (26.0, 40.0), (26.0, 49.0), (26.0, 50.0), (26.0, 51.0), (26.0, 52.0), (26.0, 53.0), (26.0, 54.0), (26.0, 66.0), (26.0, 67.0), (26.0, 68.0), (26.0, 69.0), (26.0, 70.0), (26.0, 71.0), (26.0, 72.0), (26.0, 85.0), (26.0, 92.0), (26.0, 93.0), (26.0, 99.0), (25.0, 0.0), (25.0, 11.0), (25.0, 12.0), (25.0, 13.0), (25.0, 14.0), (25.0, 15.0), (25.0, 16.0), (25.0, 17.0), (25.0, 18.0), (25.0, 19.0), (25.0, 20.0), (25.0, 21.0), (25.0, 22.0), (25.0, 23.0), (25.0, 24.0), (25.0, 25.0), (25.0, 26.0), (25.0, 32.0), (25.0, 33.0), (25.0, 39.0), (25.0, 40.0), (25.0, 65.0), (25.0, 66.0), (25.0, 67.0), (25.0, 68.0), (25.0, 69.0), (25.0, 70.0), (25.0, 71.0), (25.0, 72.0), (25.0, 85.0), (25.0, 92.0), (25.0, 93.0), (25.0, 99.0), (24.0, 0.0), (24.0, 39.0), (24.0, 40.0), (24.0, 65.0), (24.0, 66.0), (24.0, 67.0), (24.0, 68.0), (24.0, 69.0), (24.0, 70.0), (24.0, 71.0), (24.0, 72.0), (24.0, 78.0), (24.0, 79.0), (24.0, 92.0), (24.0, 93.0), (24.0, 99.0), (23.0, 0.0), (23.0, 39.0), (23.0, 40.0), (23.0, 65.0), (23.0, 66.0), (23.0, 67.0), (23.0, 68.0), (23.0, 69.0), (23.0, 70.0), (23.0, 71.0), (23.0, 72.0), (23.0, 78.0), (23.0, 79.0), (23.0, 80.0), (23.0, 92.0), (23.0, 93.0), (23.0, 99.0), (22.0, 0.0), (22.0, 39.0), (22.0, 40.0), (22.0, 67.0), (22.0, 68.0), (22.0, 69.0), (22.0, 70.0), (22.0, 71.0), (22.0, 78.0), (22.0, 79.0), (22.0, 80.0), (22.0, 91.0), (22.0, 92.0), (22.0, 93.0), (22.0, 99.0), (21.0, 0.0), (21.0, 39.0), (21.0, 40.0), (21.0, 52.0), (21.0, 53.0), (21.0, 54.0), (21.0, 55.0), (21.0, 56.0), (21.0, 57.0), (21.0, 69.0), (21.0, 70.0), (21.0, 78.0), (21.0, 79.0), (21.0, 80.0), (21.0, 81.0), (21.0, 82.0), (21.0, 91.0), (21.0, 92.0), (21.0, 93.0), (21.0, 99.0), (20.0, 0.0), (20.0, 39.0), (20.0, 40.0), (20.0, 50.0), (20.0, 51.0), (20.0, 52.0), (20.0, 53.0), (20.0, 54.0), (20.0, 55.0), (20.0, 56.0), (20.0, 57.0), (20.0, 58.0), (20.0, 77.0), (20.0, 78.0), (20.0, 79.0), (20.0, 80.0), (20.0, 81.0), (20.0, 82.0), (20.0, 83.0), (20.0, 90.0), (20.0, 91.0), (20.0, 92.0), (20.0, 99.0), (19.0, 0.0), (19.0, 5.0), (19.0, 6.0), (19.0, 7.0), (19.0, 8.0), (19.0, 9.0), (19.0, 10.0), (19.0, 11.0), (19.0, 12.0), (19.0, 13.0), (19.0, 14.0), (19.0, 15.0), (19.0, 16.0), (19.0, 17.0), (19.0, 18.0), (19.0, 19.0), (19.0, 20.0), (19.0, 21.0), (19.0, 22.0), (19.0, 23.0), (19.0, 24.0), (19.0, 25.0), (19.0, 26.0), (19.0, 27.0), (19.0, 28.0), (19.0, 29.0), (19.0, 30.0), (19.0, 31.0), (19.0, 32.0), (19.0, 33.0), (19.0, 34.0), (19.0, 35.0), (19.0, 36.0), (19.0, 37.0), (19.0, 38.0), (19.0, 39.0), (19.0, 40.0), (19.0, 41.0), (19.0, 42.0), (19.0, 43.0), (19.0, 44.0), (19.0, 45.0), (19.0, 46.0), (19.0, 47.0), (19.0, 48.0), (19.0, 49.0), (19.0, 50.0), (19.0, 51.0), (19.0, 52.0), (19.0, 53.0), (19.0, 54.0), (19.0, 55.0), (19.0, 56.0), (19.0, 57.0), (19.0, 58.0), (19.0, 77.0), (19.0, 78.0), (19.0, 79.0), (19.0, 80.0), (19.0, 81.0), (19.0, 82.0), (19.0, 83.0), (19.0, 84.0), (19.0, 90.0), (19.0, 91.0), (19.0, 92.0), (19.0, 99.0), (18.0, 0.0), (18.0, 5.0), (18.0, 6.0), (18.0, 7.0), (18.0, 8.0), (18.0, 9.0), (18.0, 10.0), (18.0, 11.0), (18.0, 12.0), (18.0, 13.0), (18.0, 14.0), (18.0, 15.0), (18.0, 16.0), (18.0, 17.0), (18.0, 18.0), (18.0, 19.0), (18.0, 20.0), (18.0, 21.0), (18.0, 22.0), (18.0, 23.0), (18.0, 24.0), (18.0, 25.0), (18.0, 26.0), (18.0, 27.0), (18.0, 28.0), (18.0, 29.0), (18.0, 30.0), (18.0, 31.0), (18.0, 32.0), (18.0, 33.0), (18.0, 34.0), (18.0, 35.0), (18.0, 36.0), (18.0, 37.0), (18.0, 38.0), (18.0, 39.0), (18.0, 40.0), (18.0, 41.0), (18.0, 42.0), (18.0, 43.0), (18.0, 44.0), (18.0, 45.0), (18.0, 46.0), (18.0, 47.0), (18.0, 48.0), (18.0, 49.0), (18.0, 50.0), (18.0, 51.0), (18.0, 52.0), (18.0, 53.0), (18.0, 54.0), (18.0, 55.0), (18.0, 56.0), (18.0, 57.0), (18.0, 58.0), (18.0, 76.0), (18.0, 77.0), (18.0, 78.0), (18.0, 79.0), (18.0, 80.0), (18.0, 81.0), (18.0, 82.0), (18.0, 83.0), (18.0, 84.0), (18.0, 99.0), (17.0, 0.0), (17.0, 5.0), (17.0, 6.0), (17.0, 7.0), (17.0, 8.0), (17.0, 9.0), (17.0, 10.0), (17.0, 11.0), (17.0, 12.0), (17.0, 13.0), (17.0, 14.0), (17.0, 15.0), (17.0, 16.0), (17.0, 17.0), (17.0, 18.0), (17.0, 19.0), (17.0, 20.0), (17.0, 21.0), (17.0, 22.0), (17.0, 23.0), (17.0, 24.0), (17.0, 25.0), (17.0, 26.0), (17.0, 27.0), (17.0, 28.0), (17.0, 29.0), (17.0, 30.0), (17.0, 31.0), (17.0, 32.0), (17.0, 33.0), (17.0, 34.0), (17.0, 35.0), (17.0, 36.0), (17.0, 37.0), (17.0, 38.0), (17.0, 39.0), (17.0, 40.0), (17.0, 41.0), (17.0, 42.0), (17.0, 43.0), (17.0, 44.0), (17.0, 45.0), (17.0, 46.0), (17.0, 47.0), (17.0, 48.0), (17.0, 49.0), (17.0, 50.0), (17.0, 51.0), (17.0, 52.0), (17.0, 53.0), (17.0, 54.0), (17.0, 55.0), (17.0, 56.0), (17.0, 57.0), (17.0, 58.0), (17.0, 65.0), (17.0, 75.0), (17.0, 76.0), (17.0, 77.0), (17.0, 78.0), (17.0, 79.0), (17.0, 80.0), (17.0, 81.0), (17.0, 82.0), (17.0, 83.0), (17.0, 84.0), (17.0, 98.0), (17.0, 99.0), (16.0, 0.0), (16.0, 5.0), (16.0, 6.0), (16.0, 7.0), (16.0, 8.0), (16.0, 9.0), (16.0, 10.0), (16.0, 11.0), (16.0, 12.0), (16.0, 13.0), (16.0, 14.0), (16.0, 15.0), (16.0, 16.0), (16.0, 17.0), (16.0, 18.0), (16.0, 19.0), (16.0, 20.0), (16.0, 21.0), (16.0, 22.0), (16.0, 23.0), (16.0, 24.0), (16.0, 25.0), (16.0, 26.0), (16.0, 27.0), (16.0, 28.0), (16.0, 29.0), (16.0, 30.0), (16.0, 31.0), (16.0, 32.0), (16.0, 33.0), (16.0, 34.0), (16.0, 35.0), (16.0, 36.0), (16.0, 37.0), (16.0, 38.0), (16.0, 39.0), (16.0, 40.0), (16.0, 41.0), (16.0, 42.0), (16.0, 43.0), (16.0, 44.0), (16.0, 45.0), (16.0, 46.0), (16.0, 47.0), (16.0, 48.0), (16.0, 49.0), (16.0, 50.0), (16.0, 51.0), (16.0, 52.0), (16.0, 53.0), (16.0, 54.0), (16.0, 55.0), (16.0, 56.0), (16.0, 57.0), (16.0, 58.0), (16.0, 65.0), (16.0, 66.0), (16.0, 67.0), (16.0, 68.0), (16.0, 69.0), (16.0, 75.0), (16.0, 76.0), (16.0, 77.0), (16.0, 78.0), (16.0, 98.0), (16.0, 99.0), (15.0, 0.0), (15.0, 51.0), (15.0, 52.0), (15.0, 53.0), (15.0, 54.0), (15.0, 55.0), (15.0, 65.0), (15.0, 66.0), (15.0, 67.0), (15.0, 68.0), (15.0, 69.0), (15.0, 97.0), (15.0, 98.0), (15.0, 99.0), (14.0, 0.0), (14.0, 52.0), (14.0, 53.0), (14.0, 65.0), (14.0, 66.0), (14.0, 67.0), (14.0, 68.0), (14.0, 69.0), (14.0, 97.0), (14.0, 98.0), (14.0, 99.0), (13.0, 0.0), (13.0, 65.0), (13.0, 66.0), (13.0, 67.0), (13.0, 68.0), (13.0, 89.0), (13.0, 96.0), (13.0, 97.0), (13.0, 98.0), (13.0, 99.0), (12.0, 0.0), (12.0, 66.0), (12.0, 67.0), (12.0, 68.0), (12.0, 75.0), (12.0, 76.0), (12.0, 77.0), (12.0, 78.0), (12.0, 79.0), (12.0, 80.0), (12.0, 81.0), (12.0, 82.0), (12.0, 88.0), (12.0, 89.0), (12.0, 90.0), (12.0, 96.0), (12.0, 97.0), (12.0, 98.0), (12.0, 99.0), (11.0, 0.0), (11.0, 67.0), (11.0, 68.0), (11.0, 69.0), (11.0, 76.0), (11.0, 77.0), (11.0, 78.0), (11.0, 79.0), (11.0, 80.0), (11.0, 86.0), (11.0, 87.0), (11.0, 88.0), (11.0, 89.0), (11.0, 90.0), (11.0, 91.0), (11.0, 97.0), (11.0, 98.0), (11.0, 99.0), (10.0, 0.0), (10.0, 68.0), (10.0, 69.0), (10.0, 77.0), (10.0, 78.0), (10.0, 79.0), (10.0, 85.0), (10.0, 86.0), (10.0, 87.0), (10.0, 88.0), (10.0, 89.0), (10.0, 90.0), (10.0, 91.0), (10.0, 98.0), (10.0, 99.0), (9.0, 0.0), (9.0, 10.0), (9.0, 11.0), (9.0, 12.0), (9.0, 13.0), (9.0, 14.0), (9.0, 15.0), (9.0, 16.0), (9.0, 17.0), (9.0, 18.0), (9.0, 19.0), (9.0, 20.0), (9.0, 21.0), (9.0, 22.0), (9.0, 23.0), (9.0, 24.0), (9.0, 25.0), (9.0, 26.0), (9.0, 27.0), (9.0, 28.0), (9.0, 29.0), (9.0, 30.0), (9.0, 31.0), (9.0, 32.0), (9.0, 33.0), (9.0, 34.0), (9.0, 35.0), (9.0, 36.0), (9.0, 37.0), (9.0, 38.0), (9.0, 41.0), (9.0, 42.0), (9.0, 43.0), (9.0, 44.0), (9.0, 45.0), (9.0, 46.0), (9.0, 47.0), (9.0, 48.0), (9.0, 49.0), (9.0, 50.0), (9.0, 51.0), (9.0, 52.0), (9.0, 53.0), (9.0, 68.0), (9.0, 69.0), (9.0, 70.0), (9.0, 75.0), (9.0, 76.0), (9.0, 77.0), (9.0, 78.0), (9.0, 79.0), (9.0, 85.0), (9.0, 86.0), (9.0, 87.0), (9.0, 88.0), (9.0, 89.0), (9.0, 90.0), (9.0, 91.0), (9.0, 98.0), (9.0, 99.0), (8.0, 0.0), (8.0, 10.0), (8.0, 11.0), (8.0, 12.0), (8.0, 13.0), (8.0, 14.0), (8.0, 15.0), (8.0, 16.0), (8.0, 17.0), (8.0, 18.0), (8.0, 19.0), (8.0, 20.0), (8.0, 21.0), (8.0, 22.0), (8.0, 23.0), (8.0, 24.0), (8.0, 25.0), (8.0, 26.0), (8.0, 27.0), (8.0, 28.0), (8.0, 29.0), (8.0, 30.0), (8.0, 31.0), (8.0, 32.0), (8.0, 33.0), (8.0, 34.0), (8.0, 35.0), (8.0, 36.0), (8.0, 37.0), (8.0, 38.0), (8.0, 41.0), (8.0, 42.0), (8.0, 43.0), (8.0, 44.0), (8.0, 45.0), (8.0, 46.0), (8.0, 47.0), (8.0, 48.0), (8.0, 49.0), (8.0, 50.0), (8.0, 51.0), (8.0, 52.0), (8.0, 69.0), (8.0, 70.0), (8.0, 71.0), (8.0, 72.0), (8.0, 73.0), (8.0, 74.0), (8.0, 75.0), (8.0, 76.0), (8.0, 77.0), (8.0, 78.0), (8.0, 84.0), (8.0, 85.0), (8.0, 86.0), (8.0, 87.0), (8.0, 88.0), (8.0, 89.0), (8.0, 90.0), (8.0, 98.0), (8.0, 99.0), (7.0, 0.0), (7.0, 10.0), (7.0, 11.0), (7.0, 12.0), (7.0, 13.0), (7.0, 14.0), (7.0, 15.0), (7.0, 16.0), (7.0, 17.0), (7.0, 18.0), (7.0, 19.0), (7.0, 20.0), (7.0, 21.0), (7.0, 22.0), (7.0, 23.0), (7.0, 24.0), (7.0, 25.0), (7.0, 26.0), (7.0, 27.0), (7.0, 28.0), (7.0, 29.0), (7.0, 30.0), (7.0, 31.0), (7.0, 32.0), (7.0, 33.0), (7.0, 34.0), (7.0, 35.0), (7.0, 36.0), (7.0, 37.0), (7.0, 38.0), (7.0, 41.0), (7.0, 42.0), (7.0, 43.0), (7.0, 44.0), (7.0, 45.0), (7.0, 46.0), (7.0, 47.0), (7.0, 48.0), (7.0, 49.0), (7.0, 50.0), (7.0, 51.0), (7.0, 59.0), (7.0, 60.0), (7.0, 70.0), (7.0, 71.0), (7.0, 72.0), (7.0, 73.0), (7.0, 74.0), (7.0, 84.0), (7.0, 85.0), (7.0, 86.0), (7.0, 87.0), (7.0, 88.0), (7.0, 89.0), (7.0, 97.0), (7.0, 98.0), (7.0, 99.0), (6.0, 0.0), (6.0, 45.0), (6.0, 46.0), (6.0, 47.0), (6.0, 48.0), (6.0, 49.0), (6.0, 57.0), (6.0, 58.0), (6.0, 59.0), (6.0, 60.0), (6.0, 61.0), (6.0, 62.0), (6.0, 95.0), (6.0, 96.0), (6.0, 97.0), (6.0, 98.0), (6.0, 99.0), (5.0, 0.0), (5.0, 56.0), (5.0, 57.0), (5.0, 58.0), (5.0, 59.0), (5.0, 60.0), (5.0, 61.0), (5.0, 62.0), (5.0, 63.0), (5.0, 94.0), (5.0, 95.0), (5.0, 96.0), (5.0, 97.0), (5.0, 98.0), (5.0, 99.0), (4.0, 0.0), (4.0, 54.0), (4.0, 55.0), (4.0, 56.0), (4.0, 57.0), (4.0, 58.0), (4.0, 59.0), (4.0, 60.0), (4.0, 61.0), (4.0, 62.0), (4.0, 63.0), (4.0, 79.0), (4.0, 92.0), (4.0, 93.0), (4.0, 94.0), (4.0, 95.0), (4.0, 96.0), (4.0, 97.0), (4.0, 98.0), (4.0, 99.0), (3.0, 0.0), (3.0, 54.0), (3.0, 55.0), (3.0, 56.0), (3.0, 57.0), (3.0, 58.0), (3.0, 59.0), (3.0, 60.0), (3.0, 61.0), (3.0, 62.0), (3.0, 77.0), (3.0, 78.0), (3.0, 79.0), (3.0, 80.0), (3.0, 90.0), (3.0, 91.0), (3.0, 92.0), (3.0, 93.0), (3.0, 94.0), (3.0, 95.0), (3.0, 96.0), (3.0, 97.0), (3.0, 98.0), (3.0, 99.0), (2.0, 0.0), (2.0, 53.0), (2.0, 54.0), (2.0, 55.0), (2.0, 56.0), (2.0, 57.0), (2.0, 76.0), (2.0, 77.0), (2.0, 78.0), (2.0, 79.0), (2.0, 80.0), (2.0, 88.0), (2.0, 89.0), (2.0, 90.0), (2.0, 91.0), (2.0, 92.0), (2.0, 93.0), (2.0, 94.0), (2.0, 95.0), (2.0, 96.0), (2.0, 97.0), (2.0, 98.0), (2.0, 99.0), (1.0, 0.0), (1.0, 52.0), (1.0, 53.0), (1.0, 54.0), (1.0, 55.0), (1.0, 75.0), (1.0, 76.0), (1.0, 77.0), (1.0, 78.0), (1.0, 79.0), (1.0, 80.0), (1.0, 81.0), (1.0, 87.0), (1.0, 88.0), (1.0, 89.0), (1.0, 90.0), (1.0, 91.0), (1.0, 92.0), (1.0, 93.0), (1.0, 94.0), (1.0, 95.0), (1.0, 96.0), (1.0, 97.0), (1.0, 98.0), (1.0, 99.0), (0.0, 0.0), (0.0, 1.0), (0.0, 2.0), (0.0, 3.0), (0.0, 4.0), (0.0, 5.0), (0.0, 6.0), (0.0, 7.0), (0.0, 8.0), (0.0, 9.0), (0.0, 10.0), (0.0, 11.0), (0.0, 12.0), (0.0, 13.0), (0.0, 14.0), (0.0, 15.0), (0.0, 16.0), (0.0, 17.0), (0.0, 18.0), (0.0, 19.0), (0.0, 20.0), (0.0, 21.0), (0.0, 22.0), (0.0, 23.0), (0.0, 24.0), (0.0, 25.0), (0.0, 26.0), (0.0, 27.0), (0.0, 28.0), (0.0, 29.0), (0.0, 30.0), (0.0, 31.0), (0.0, 32.0), (0.0, 33.0), (0.0, 34.0), (0.0, 35.0), (0.0, 36.0), (0.0, 37.0), (0.0, 38.0), (0.0, 39.0), (0.0, 40.0), (0.0, 41.0), (0.0, 42.0), (0.0, 43.0), (0.0, 44.0), (0.0, 45.0), (0.0, 46.0), (0.0, 47.0), (0.0, 48.0), (0.0, 49.0), (0.0, 50.0), (0.0, 51.0), (0.0, 52.0), (0.0, 53.0), (0.0, 54.0), (0.0, 55.0), (0.0, 56.0), (0.0, 57.0), (0.0, 58.0), (0.0, 59.0), (0.0, 60.0), (0.0, 61.0), (0.0, 62.0), (0.0, 63.0), (0.0, 64.0), (0.0, 65.0), (0.0, 66.0), (0.0, 67.0), (0.0, 68.0), (0.0, 69.0), (0.0, 70.0), (0.0, 71.0), (0.0, 72.0), (0.0, 73.0), (0.0, 74.0), (0.0, 75.0), (0.0, 76.0), (0.0, 77.0), (0.0, 78.0), (0.0, 79.0), (0.0, 80.0), (0.0, 81.0), (0.0, 82.0), (0.0, 83.0), (0.0, 84.0), (0.0, 85.0), (0.0, 86.0), (0.0, 87.0), (0.0, 88.0), (0.0, 89.0), (0.0, 90.0), (0.0, 91.0), (0.0, 92.0), (0.0, 93.0), (0.0, 94.0), (0.0, 95.0), (0.0, 96.0), (0.0, 97.0), (0.0, 98.0), (0.0, 99.0)];
 
 #[derive(Debug)]
@@ -59,6 +77,24 @@ impl<'a> TabsState<'a> {
 
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    // Where the robot can be put down at the start.
+    Start,
+    // Where it should drive to.
+    Goal,
+    // Anything else worth knowing about, e.g. a door or a charging station.
+    Landmark,
+}
+
+// A named spot on the map. The heading is 0 if the map doesn't give one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub name: String,
+    pub kind: MarkerKind,
+    pub pose: Vector3<f64>,
+}
+
 #[derive(Debug)]
 pub struct World<'a> {
     pub name: &'a str,
@@ -66,14 +102,29 @@ pub struct World<'a> {
     pub max: Option<Vector2<f64>>,
     pub location: Option<Vector2<f64>>,
     pub wall_list: Vec<Vector2<f64>>,
+    pub markers: Vec<Marker>,
 }
 
 impl<'a> World<'a> {
     pub fn new(name: &'a str, location: Option<Vector2<f64>>, wall_list: Vec<Vector2<f64>>) -> World<'a> {
-        let mut t = World {name: name, min: None, max: None, location, wall_list};
+        let mut t = World {name: name, min: None, max: None, location, wall_list, markers: Vec::new()};
         t.update_min_max();
         t
     }
+
+    pub fn markers_of(&self, kind: MarkerKind) -> impl Iterator<Item = &Marker> {
+        self.markers.iter().filter(move |m| m.kind == kind)
+    }
+
+    // The start marker with the name, or the first one without a name.
+    pub fn start(&self, name: Option<&str>) -> Option<&Marker> {
+        let mut starts = self.markers_of(MarkerKind::Start);
+        match name {
+            Some(n) => starts.find(|m| m.name == n),
+            None => starts.next(),
+        }
+    }
+
     // add a new wall point and update the min max values.
     pub fn add_wall_point(&mut self, new_wall_point: Vector2<f64>) {
         self.wall_list.push(new_wall_point);
@@ -146,14 +197,30 @@ pub struct App<'a>{
     world: World<'a>,
     // Stores the Access to the Hardware or its simulation.
     my_pi: robo::MyPi,
+    // Number of times the robot bumped into a wall and the last of these bumps.
+    collisions: usize,
+    last_collision: Option<robo::Collision>,
+    // Number of failed sensor readings and the last of these errors.
+    sensor_errors: usize,
+    last_sensor_error: Option<robo::SensorError>,
+    // Number of failed motor commands and the last of these errors. Only the robot has them.
+    motor_errors: usize,
+    last_motor_error: Option<String>,
+    // Estimators of the pose from the sensor and the wheel velocities. Only the active one runs.
+    localizers: Vec<Box<dyn Localizer>>,
+    active_localizer: usize,
+    // Error of the active localizer against the ground truth. Only the simulation has one.
+    metrics: Option<Metrics>,
+    // Time between two updates.
+    tick_rate: Duration,
     // True if we want to close the app.
     exit: bool,
 }
 
 impl<'a> App<'a> {
-    pub fn new(my_pi: robo::MyPi) -> Self {
+    pub fn new(my_pi: robo::MyPi, localizers: Vec<Box<dyn Localizer>>, world: World<'a>) -> Self {
         App {
-            tabs: TabsState::new(vec!["Map", "Sensor"]),
+            tabs: TabsState::new(vec!["Map", "Sensor", "Metrics"]),
             sens_data: false,
             ring_buf: VecDeque::from(vec![0.; SIZE_RINGBUFF_DIST]
                 .into_iter()
@@ -161,17 +228,31 @@ impl<'a> App<'a> {
                 .map(|(u, f)| (u as f64, f))
                 .collect::<Vec<(f64,f64)>>()),
             mean: 0.,
-            // world: World { name: "small",min: (0.,0.),max: (39.,39.) , location: (2.,6.), wall_list: WALL_SMALL.to_vec()},
-            // world: World::new("small", None,  WALL_SMALL.to_vec().iter().map(|(x,y)| Vector2::new(*x,*y)).collect::<Vec<Vector2<f64>>>()),
-            world: World::new("big", None,  WALL_BIG.to_vec().iter().map(|(x,y)| Vector2::new(*x,*y)).collect::<Vec<Vector2<f64>>>()),
-            //world: World { name: "big",min: (0.,0.),max: (99.,99.) , location: (5.,20.), wall_list: WALL_BIG.to_vec()},
+            world,
+            collisions: 0,
+            last_collision: None,
+            sensor_errors: 0,
+            last_sensor_error: None,
+            motor_errors: 0,
+            last_motor_error: None,
+            metrics: match my_pi {
+                robo::MyPi::Sim(_) => Some(Metrics::new()),
+                robo::MyPi::Real(_) => None,
+            },
             my_pi,
+            localizers,
+            active_localizer: 0,
+            tick_rate: Duration::from_millis(125),
             exit: false }
     }
 
+    pub fn set_tick_rate(&mut self, tick_rate: Duration) {
+        self.tick_rate = tick_rate;
+    }
+
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         // This is the rate of update.
-        let tick_rate = Duration::from_millis(125);
+        let tick_rate = self.tick_rate;
         // This stores the time of last update.
         let mut last_tick = Instant::now();
         // This is the main loop.
@@ -194,8 +275,14 @@ impl<'a> App<'a> {
                             KeyCode::Char('q') => self.exit(),
                             KeyCode::Char('w') => self.deactivate_sensor(),
                             KeyCode::Char('e') => self.activate_sensor(),
-                            KeyCode::Left => self.lower_tab(),
-                            KeyCode::Right => self.raise_tab(), 
+                            KeyCode::Char('r') => self.stop(),
+                            KeyCode::Char('l') => self.next_localizer(),
+                            KeyCode::Up => self.change_velo(VELO_STEP, VELO_STEP),
+                            KeyCode::Down => self.change_velo(-VELO_STEP, -VELO_STEP),
+                            KeyCode::Left => self.change_velo(-VELO_STEP, VELO_STEP),
+                            KeyCode::Right => self.change_velo(VELO_STEP, -VELO_STEP),
+                            KeyCode::BackTab => self.lower_tab(),
+                            KeyCode::Tab => self.raise_tab(),
                             _ => {}
                         }
                     }
@@ -205,7 +292,39 @@ impl<'a> App<'a> {
             // If the time since the last update is larger than the tick rate
             // we need to get a new measurment.
             if last_tick.elapsed() >= tick_rate {
-                if self.sens_data {
+                self.tick(last_tick.elapsed().as_secs_f64());
+                last_tick = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    // Moves the robot and the estimate by dt seconds and takes a measurement if the sensor is on.
+    fn tick(&mut self, dt: f64) {
+        // Move the robot and the estimate by the time that passed since the last update.
+        self.my_pi.step(dt);
+        // Predict with what the wheels really did, not with what we asked for.
+        let (vl, vr) = self.my_pi.get_measured_wheel_velo();
+        if let Some(l) = self.localizers.get_mut(self.active_localizer) {
+            l.predict(vl, vr, dt);
+        }
+        let p = self.my_pi.robot_position();
+        self.world.location = Some(Vector2::new(p.x, p.y));
+        if let Some(c) = self.my_pi.take_collision() {
+            self.collisions += 1;
+            self.last_collision = Some(c);
+        }
+        let (n, e) = self.my_pi.take_motor_errors();
+        if n > 0 {
+            self.motor_errors += n;
+            self.last_motor_error = e;
+        }
+        if self.sens_data {
+            match self.my_pi.get_hcsr04_dist() {
+                Ok(dist) => {
+                    if let Some(l) = self.localizers.get_mut(self.active_localizer) {
+                        l.update(dist);
+                    }
                     // remove the oldest element.
                     let (_, ov ) = self.ring_buf.pop_back().unwrap();
                     // Get the index of the newest element by getting the seconde newest 
@@ -214,17 +333,66 @@ impl<'a> App<'a> {
                         Some((i, _)) => *i + 1.,
                         None => 0.,
                     };
-                    // 
-                    match self.my_pi.get_hcsr04_dist() {
+                    match dist {
                         Some(v) => self.ring_buf.push_front((idx ,v)),
                         None => self.ring_buf.push_front((idx,self.my_pi.get_hcsr04_max_range())),
                     }
                     self.mean = self.mean + (self.ring_buf.front().unwrap().1 - ov) / SIZE_RINGBUFF_DIST as f64
-                }
-                last_tick = Instant::now();
+                },
+                // A failed reading tells us nothing, so the localizer doesn't see it.
+                Err(e) => {
+                    self.sensor_errors += 1;
+                    self.last_sensor_error = Some(e);
+                },
             }
         }
-        Ok(())
+        if let (Some(m), Some(l)) = (self.metrics.as_mut(), self.localizers.get(self.active_localizer)) {
+            let (mean, covariance) = l.estimate();
+            m.record(dt, self.my_pi.robot_position(), mean, covariance);
+        }
+    }
+
+    // Runs without the terminal UI: drives with the given wheel velocities and the sensor on,
+    // prints the error of the estimate every second and a summary at the end.
+    pub fn run_headless(&mut self, duration: Duration, velo: (f64, f64)) {
+        self.activate_sensor();
+        self.my_pi.set_wheel_velo(velo.0, velo.1);
+        let name = self.localizers.get(self.active_localizer).map_or("none", |l| l.name());
+        println!("Localizer: {}, map: {}", name, self.world.name);
+        let start = Instant::now();
+        let mut last_tick = start;
+        let mut last_print = 0.;
+        while start.elapsed() < duration {
+            std::thread::sleep(self.tick_rate.saturating_sub(last_tick.elapsed()));
+            let dt = last_tick.elapsed().as_secs_f64();
+            last_tick = Instant::now();
+            self.tick(dt);
+            let t = start.elapsed().as_secs_f64();
+            if t - last_print >= 1. {
+                last_print = t;
+                println!("{}", self.headless_line(t));
+            }
+        }
+        self.stop();
+        if let Some(m) = &self.metrics {
+            let fmt = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+            println!("Position RMSE: {}, heading RMSE: {}", fmt(m.position_rmse()), fmt(m.heading_rmse()));
+            println!("Mean NEES: {}, inside 95 % bound: {} %", fmt(m.mean_nees()), fmt(m.nees_consistency().map(|c| c * 100.)));
+            println!("Converged after: {} s", fmt(m.time_to_convergence()));
+        }
+        println!("Collisions: {}, sensor errors: {}, motor errors: {}", self.collisions, self.sensor_errors, self.motor_errors);
+    }
+
+    fn headless_line(&self, t: f64) -> String {
+        let (mean, _) = self.localizers.get(self.active_localizer).map_or((Vector3::zeros(), Matrix3::zeros()), |l| l.estimate());
+        let mut line = format!("t={:6.1} estimate=({:.2}, {:.2}, {:.2})", t, mean.x, mean.y, mean.z);
+        if let Some(e) = self.metrics.as_ref().and_then(|m| m.last()) {
+            line += &format!(" position_error={:.3} heading_error={:.3}", e.position_error, e.heading_error);
+            if let Some(nees) = e.nees {
+                line += &format!(" nees={:.2}", nees);
+            }
+        }
+        line
     }
 
     pub fn draw(&self, frame: &mut Frame) {
@@ -236,6 +404,7 @@ impl<'a> App<'a> {
         match self.tabs.index {
             0 => self.render_map(frame, right),
             1 => self.render_map(frame, right),
+            2 => self.render_metrics(frame, right),
             _ => panic!("unkown tab id")
         };
         
@@ -246,8 +415,12 @@ impl<'a> App<'a> {
         let instructions = Line::from(vec![
             " Move ".into(),
             "<Arrows>".blue().bold(),
-            " reset ".into(),
+            " Stop ".into(),
             "<r>".blue().bold(),
+            " Localizer ".into(),
+            "<l>".blue().bold(),
+            " Tab ".into(),
+            "<Tab>".blue().bold(),
             " Quit ".into(),
             "<Q> ".blue().bold(),
         ]);
@@ -257,10 +430,33 @@ impl<'a> App<'a> {
             .border_set(border::THICK);
         
         let (vl, vr) = self.my_pi.get_wheel_velo();
+        let (ml, mr) = self.my_pi.get_measured_wheel_velo();
         let p = self.my_pi.robot_position();
+        let o = self.my_pi.odometry_position();
+        let (name, (e, cov)) = match self.localizers.get(self.active_localizer) {
+            Some(l) => (l.name(), l.estimate()),
+            None => ("none", (Vector3::zeros(), Matrix3::zeros())),
+        };
         let counter_text: Text<'_> = Text::from(
-            vec![Line::from(vec!["Position: x=".into(), p.x.to_string().yellow().into(), " y=".into(), p.y.to_string().yellow().into(), ]),
-                 Line::from(vec!["Wheel velo:  x=".into(), vl.to_string().yellow(), " y=".into(), vr.to_string().yellow()])
+            vec![Line::from(vec!["Position: x=".into(), format!("{:.2}", p.x).yellow(), " y=".into(), format!("{:.2}", p.y).yellow(), " theta=".into(), format!("{:.2}", p.z).yellow()]),
+                 Line::from(vec!["Odometry: x=".into(), format!("{:.2}", o.x).yellow(), " y=".into(), format!("{:.2}", o.y).yellow(), " theta=".into(), format!("{:.2}", o.z).yellow()]),
+                 Line::from(vec!["Localizer: ".into(), name.magenta()]),
+                 Line::from(vec!["Estimate: x=".into(), format!("{:.2}", e.x).magenta(), " y=".into(), format!("{:.2}", e.y).magenta(), " theta=".into(), format!("{:.2}", e.z).magenta()]),
+                 Line::from(vec!["Std dev:  x=".into(), format!("{:.2}", cov[(0, 0)].sqrt()).magenta(), " y=".into(), format!("{:.2}", cov[(1, 1)].sqrt()).magenta(), " theta=".into(), format!("{:.2}", cov[(2, 2)].sqrt()).magenta()]),
+                 Line::from(vec!["Wheel velo:  left=".into(), vl.to_string().yellow(), " right=".into(), vr.to_string().yellow()]),
+                 Line::from(vec!["Measured:    left=".into(), format!("{:.2}", ml).yellow(), " right=".into(), format!("{:.2}", mr).yellow()]),
+                 match self.last_collision {
+                    Some(c) => Line::from(vec!["Bumper: ".into(), self.collisions.to_string().red(), " hits, last at x=".into(), format!("{:.2}", c.pose.x).red(), " y=".into(), format!("{:.2}", c.pose.y).red()]),
+                    None => Line::from(vec!["Bumper: ".into(), "no hits".green()]),
+                 },
+                 match &self.last_sensor_error {
+                    Some(e) => Line::from(vec!["Sensor: ".into(), self.sensor_errors.to_string().red(), " errors, last: ".into(), e.to_string().red()]),
+                    None => Line::from(vec!["Sensor: ".into(), "no errors".green()]),
+                 },
+                 match &self.last_motor_error {
+                    Some(e) => Line::from(vec!["Motors: ".into(), self.motor_errors.to_string().red(), " errors, last: ".into(), e.clone().red()]),
+                    None => Line::from(vec!["Motors: ".into(), "no errors".green()]),
+                 },
                  ]);  
         
         let [left_top, left_bot] = Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
@@ -318,6 +514,75 @@ impl<'a> App<'a> {
         frame.render_widget(chart, area);
     }
 
+    // Accuracy of the active localizer: numbers on top and the errors over time below.
+    fn render_metrics(&self, frame: &mut Frame, area: Rect) {
+        let m = match &self.metrics {
+            Some(m) => m,
+            None => {
+                let p = Paragraph::new("No ground truth on the real robot.")
+                    .centered()
+                    .block(Block::bordered().title("Metrics"));
+                frame.render_widget(p, area);
+                return;
+            },
+        };
+        let [top, bot] = Layout::vertical([Constraint::Length(9), Constraint::Fill(1)]).areas(area);
+
+        let fmt = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+        let name = self.localizers.get(self.active_localizer).map_or("none", |l| l.name());
+        let last = m.last();
+        let text = Text::from(vec![
+            Line::from(vec!["Localizer: ".into(), name.magenta(), " running for ".into(), format!("{:.1} s", m.time()).yellow()]),
+            Line::from(vec!["Position error: ".into(), fmt(last.map(|l| l.position_error)).yellow(), " RMSE: ".into(), fmt(m.position_rmse()).yellow()]),
+            Line::from(vec!["Heading error:  ".into(), fmt(last.map(|l| l.heading_error)).yellow(), " RMSE: ".into(), fmt(m.heading_rmse()).yellow()]),
+            Line::from(vec!["NEES: ".into(), fmt(last.and_then(|l| l.nees)).yellow(), " mean: ".into(), fmt(m.mean_nees()).yellow(), " (3 is consistent)".into()]),
+            Line::from(vec!["NEES inside 95 % bound: ".into(), fmt(m.nees_consistency().map(|c| c * 100.)).yellow(), " %".into()]),
+            match m.time_to_convergence() {
+                Some(t) => Line::from(vec!["Converged after ".into(), format!("{:.1} s", t).green()]),
+                None => Line::from(vec!["Converged: ".into(), "not yet".red()]),
+            },
+        ]);
+        let p = Paragraph::new(text)
+            .centered()
+            .block(Block::bordered().title("Metrics"));
+        frame.render_widget(p, top);
+
+        let position: Vec<(f64, f64)> = m.history().iter().map(|e| (e.time, e.position_error)).collect();
+        let heading: Vec<(f64, f64)> = m.history().iter().map(|e| (e.time, e.heading_error)).collect();
+        let t0 = m.history().front().map_or(0., |e| e.time);
+        let t1 = m.history().back().map_or(1., |e| e.time).max(t0 + 1.);
+        let max_err = position.iter().chain(heading.iter()).fold(1., |acc: f64, (_, e)| acc.max(*e)).ceil();
+        let datasets = vec![
+            Dataset::default()
+                .name("position")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Yellow))
+                .data(&position),
+            Dataset::default()
+                .name("heading")
+                .marker(symbols::Marker::Dot)
+                .style(Style::default().fg(Color::Magenta))
+                .data(&heading),
+        ];
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title("Error"))
+            .x_axis(
+                Axis::default()
+                    .title("time")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels([format!("{:.0}", t0).bold(), format!("{:.0}", t1).bold()])
+                    .bounds([t0, t1]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title("error")
+                    .style(Style::default().fg(Color::Gray))
+                    .labels(["0".bold(), format!("{}", max_err).bold()])
+                    .bounds([0., max_err]),
+            );
+        frame.render_widget(chart, bot);
+    }
+
     fn render_map(&self, frame: &mut Frame, area: Rect) {       
         let map = Canvas::default()
             .block(Block::bordered().title(self.world.name))
@@ -337,6 +602,34 @@ impl<'a> App<'a> {
                             },
                             None => (),
                         }
+                        if let Some(l) = self.localizers.get(self.active_localizer) {
+                            // The belief of the localizer, strong points in yellow and weak ones in blue.
+                            let samples = l.samples();
+                            let max_p = samples.iter().fold(0., |acc: f64, (_, p)| acc.max(*p));
+                            let (strong, weak): (ScreenPoints, ScreenPoints) = samples.iter()
+                                .filter(|(_, p)| *p > max_p * BELIEF_DRAW_MIN)
+                                .map(|(v, p)| ((area.x as f64 + (area.width as f64)*(v.x-self.world.min.unwrap().x)/(self.world.max.unwrap().x - self.world.min.unwrap().x),
+                                               area.y as f64 + (area.height as f64)*(v.y-self.world.min.unwrap().y)/(self.world.max.unwrap().y - self.world.min.unwrap().y)), *p))
+                                .fold((Vec::new(), Vec::new()), |mut acc, (c, p)| {
+                                    if p > max_p / 2. { acc.0.push(c) } else { acc.1.push(c) }
+                                    acc
+                                });
+                            ctx.draw(&Points{ coords: &weak, color: Color::Blue });
+                            ctx.draw(&Points{ coords: &strong, color: Color::Yellow });
+                            // And the estimated position on top.
+                            let (e, _) = l.estimate();
+                            let resized_est = (area.x as f64 + (area.width as f64)*(e.x-self.world.min.unwrap().x)/(self.world.max.unwrap().x - self.world.min.unwrap().x),
+                                               area.y as f64 + (area.height as f64)*(e.y-self.world.min.unwrap().y)/(self.world.max.unwrap().y - self.world.min.unwrap().y));
+                            ctx.draw(&Points{ coords: &[resized_est], color: Color::Magenta });
+                        }
+                        // Goals in green and landmarks in cyan.
+                        for (kind, color) in [(MarkerKind::Goal, Color::Green), (MarkerKind::Landmark, Color::Cyan)] {
+                            let resized: Vec<(f64, f64)> = self.world.markers_of(kind).map(|m| {
+                                (area.x as f64 + (area.width as f64)*(m.pose.x-self.world.min.unwrap().x)/(self.world.max.unwrap().x - self.world.min.unwrap().x),
+                                 area.y as f64 + (area.height as f64)*(m.pose.y-self.world.min.unwrap().y)/(self.world.max.unwrap().y - self.world.min.unwrap().y))
+                            }).collect();
+                            ctx.draw(&Points{ coords: &resized, color });
+                        }
                         // Same for wall points.
                         let resized_wall_list: Vec<(f64,f64)>= self.world.wall_list.iter().map(|v: &Vector2<f64>| {
                             (area.x as f64 + (area.width as f64)*(v.x-self.world.min.unwrap().x)/(self.world.max.unwrap().x - self.world.min.unwrap().x),
@@ -369,6 +662,33 @@ impl<'a> App<'a> {
         self.exit = true;
     }
 
+    // Add the given values to the wheel velocities. The backend clamps them to its limits.
+    fn change_velo(&mut self, left: f64, right: f64) {
+        let (vl, vr) = self.my_pi.get_wheel_velo();
+        let max = self.my_pi.wheel_velo_max();
+        let min = self.my_pi.wheel_velo_min();
+        self.my_pi.set_wheel_velo((vl + left).clamp(min, max), (vr + right).clamp(min, max));
+    }
+
+    fn stop(&mut self) {
+        self.my_pi.set_wheel_velo(0., 0.);
+    }
+
+    // Switches to the next localizer. It starts from the estimate of the one before,
+    // so switching doesn't lose what we already know.
+    fn next_localizer(&mut self) {
+        if self.localizers.is_empty() {
+            return;
+        }
+        let (mean, mut covariance) = self.localizers[self.active_localizer].estimate();
+        covariance += Matrix3::from_diagonal(&Vector3::new(SWITCH_MIN_VAR.0, SWITCH_MIN_VAR.0, SWITCH_MIN_VAR.1));
+        self.active_localizer = (self.active_localizer + 1) % self.localizers.len();
+        self.localizers[self.active_localizer].reset(&robo::RobotStartBelief::Gaussian { mean, covariance });
+        if let Some(m) = self.metrics.as_mut() {
+            m.reset();
+        }
+    }
+
     fn activate_sensor(&mut self) {
         self.sens_data = true
     }
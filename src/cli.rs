@@ -0,0 +1,189 @@
+use clap::Parser;
+use nalgebra::{Vector2, Vector3};
+
+use crate::app::World;
+
+// Command line flags, so experiments can run without recompiling.
+
+#[derive(Parser, Debug, Clone, PartialEq)]
+#[command(version, about = "Localizes a robot with an ultrasonic sensor, on a Raspberry Pi or in the simulation.")]
+pub struct Args {
+    /// Run the simulation, even on a Raspberry Pi.
+    #[arg(long, conflicts_with = "real")]
+    pub sim: bool,
+    /// Run on the robot. Fails if this is not a Raspberry Pi with a hardware profile.
+    #[arg(long)]
+    pub real: bool,
+    /// Robot config file (.toml, .yaml or .yml) with geometry, pins, limits, noise and filter settings.
+    #[arg(long)]
+    pub config: Option<std::path::PathBuf>,
+    /// Built-in map ("small" or "big"), a Tiled .tmx file or a file with one wall cell "x,y" per
+    /// line.
+    #[arg(long, default_value = "small")]
+    pub map: String,
+    /// Tile ids that are walls in a Tiled map.
+    #[arg(long, value_delimiter = ',', default_values_t = crate::tiled::DEFAULT_WALL_IDS.to_vec())]
+    pub wall_ids: Vec<u32>,
+    /// Tile ids that mark a start pose in a Tiled map.
+    #[arg(long, value_delimiter = ',', default_values_t = crate::tiled::DEFAULT_START_IDS.to_vec())]
+    pub start_ids: Vec<u32>,
+    /// Max range of the ultrasonic sensor in m [default: from the config, else 5 in the
+    /// simulation and 4 on the robot].
+    #[arg(long, value_parser = parse_positive)]
+    pub max_range: Option<f64>,
+    /// Time between two updates in ms.
+    #[arg(long, default_value_t = 125, value_parser = clap::value_parser!(u64).range(1..))]
+    pub tick_ms: u64,
+    /// Seed of the sensor noise and the particle filter [default: from the config, else 0].
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Start pose "x,y,theta" in m and rad. On the robot the localizers start there instead of
+    /// everywhere on the map.
+    #[arg(long, value_parser = parse_pose, allow_hyphen_values = true)]
+    pub start: Option<Vector3<f64>>,
+    /// Name of the start marker of the map to start at [default: the first one].
+    #[arg(long, conflicts_with = "start")]
+    pub start_at: Option<String>,
+    /// Box "x0,y0,x1,y1" in m the robot stands in, with any heading. The localizers start
+    /// anywhere in it instead of at the start pose.
+    #[arg(long, value_parser = parse_region, allow_hyphen_values = true)]
+    pub start_region: Option<(Vector2<f64>, Vector2<f64>)>,
+    /// Run without the terminal UI and print the metrics.
+    #[arg(long)]
+    pub headless: bool,
+    /// How long the headless run lasts in s.
+    #[arg(long, default_value_t = 30., value_parser = parse_positive)]
+    pub duration: f64,
+    /// Wheel velocities "left,right" in rad/s the robot drives with in the headless run.
+    #[arg(long, default_value = "0,0", value_parser = parse_velo, allow_hyphen_values = true)]
+    pub velo: (f64, f64),
+}
+
+fn parse_numbers(s: &str, n: usize) -> Result<Vec<f64>, String> {
+    let v = s.split(',')
+        .map(|p| p.trim().parse::<f64>().map_err(|e| format!("{:?}: {}", p.trim(), e)))
+        .collect::<Result<Vec<f64>, String>>()?;
+    if v.len() != n {
+        return Err(format!("expected {} comma separated numbers, got {}", n, v.len()));
+    }
+    if let Some(x) = v.iter().find(|x| !x.is_finite()) {
+        return Err(format!("{} is not a finite number", x));
+    }
+    Ok(v)
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    match parse_numbers(s, 1)?[0] {
+        v if v > 0. => Ok(v),
+        v => Err(format!("{} is not positive", v)),
+    }
+}
+
+pub fn parse_pose(s: &str) -> Result<Vector3<f64>, String> {
+    let v = parse_numbers(s, 3)?;
+    Ok(Vector3::new(v[0], v[1], v[2]))
+}
+
+fn parse_region(s: &str) -> Result<(Vector2<f64>, Vector2<f64>), String> {
+    let v = parse_numbers(s, 4)?;
+    match v[0] < v[2] && v[1] < v[3] {
+        true => Ok((Vector2::new(v[0], v[1]), Vector2::new(v[2], v[3]))),
+        false => Err(format!("{:?} is not the lower left and the upper right corner of a box", s)),
+    }
+}
+
+fn parse_velo(s: &str) -> Result<(f64, f64), String> {
+    let v = parse_numbers(s, 2)?;
+    Ok((v[0], v[1]))
+}
+
+// Wall cells of the map. Empty lines and lines starting with # are skipped.
+pub fn parse_wall_points(text: &str) -> Result<Vec<Vector2<f64>>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim().starts_with('#'))
+        .map(|(i, l)| parse_numbers(l, 2)
+            .map(|v| Vector2::new(v[0], v[1]))
+            .map_err(|e| format!("line {}: {}", i + 1, e)))
+        .collect()
+}
+
+impl Args {
+    // The map the flags ask for. Only Tiled maps have markers.
+    pub fn world(&self) -> Result<World<'_>, String> {
+        let points = |w: &[(f64, f64)]| w.iter().map(|(x, y)| Vector2::new(*x, *y)).collect();
+        let world = match self.map.as_str() {
+            "small" => World::new("small", None, points(&crate::app::WALL_SMALL)),
+            "big" => World::new("big", None, points(&crate::app::WALL_BIG)),
+            path if path.ends_with(".tmx") => {
+                let map = crate::tiled::TiledMap::load(std::path::Path::new(path)).map_err(|e| e.to_string())?;
+                map.world(self.map_name(), &self.wall_ids, &self.start_ids)
+            },
+            path => {
+                let text = std::fs::read_to_string(path).map_err(|e| format!("can't read map {}: {}", path, e))?;
+                let walls = parse_wall_points(&text).map_err(|e| format!("map {}: {}", path, e))?;
+                World::new(self.map_name(), None, walls)
+            },
+        };
+        // Without walls the grid has no cells and nothing can be localized.
+        let grid = crate::grid::GridMap::from_points(&world.wall_list);
+        if grid.is_empty() {
+            return Err(format!("map {} has no walls", self.map));
+        }
+        // The localizers need at least one free cell in the box to start in.
+        if let Some((min, max)) = self.start_region {
+            let inside = |c: Vector2<f64>| c.x >= min.x && c.x <= max.x && c.y >= min.y && c.y <= max.y;
+            if !grid.free_cells().into_iter().any(|(i, j)| inside(grid.cell_center(i, j))) {
+                return Err(format!("the start region has no free cell on map {}", self.map));
+            }
+        }
+        if let Some(name) = &self.start_at {
+            if world.start(Some(name)).is_none() {
+                return Err(format!("map {} has no start marker {:?}", self.map, name));
+            }
+        }
+        Ok(world)
+    }
+
+    // Name of the map for the UI, the file name without the directories.
+    pub fn map_name(&self) -> &str {
+        std::path::Path::new(&self.map).file_stem().and_then(|s| s.to_str()).unwrap_or(&self.map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flags_and_maps() {
+        let args = Args::try_parse_from(["senter", "--sim", "--start", "-1.5,2,0.5", "--seed", "7", "--tick-ms", "50"]).unwrap();
+        assert!(args.sim && !args.real && !args.headless);
+        assert_eq!(args.start, Some(Vector3::new(-1.5, 2., 0.5)));
+        assert_eq!((args.seed, args.tick_ms, args.map.as_str()), (Some(7), 50, "small"));
+        assert_eq!(args.world().unwrap().wall_list.len(), crate::app::WALL_SMALL.len());
+        // Sim and real together make no sense.
+        assert!(Args::try_parse_from(["senter", "--sim", "--real"]).is_err());
+        assert!(Args::try_parse_from(["senter", "--max-range", "-1"]).is_err());
+        assert!(Args::try_parse_from(["senter", "--start", "1,2"]).is_err());
+        assert_eq!(Args::try_parse_from(["senter", "--wall-ids", "5,6"]).unwrap().wall_ids, vec![5, 6]);
+        // The built-in maps have no markers to start at.
+        assert!(Args::try_parse_from(["senter", "--start-at", "start 1"]).unwrap().world().is_err());
+        // No tile of the map is a wall with these ids.
+        let tmx = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/map_small.tmx");
+        let err = Args::try_parse_from(["senter", "--map", tmx, "--wall-ids", "999"]).unwrap().world().unwrap_err();
+        assert!(err.ends_with("has no walls"), "{}", err);
+
+        let args = Args::try_parse_from(["senter", "--start-region", "-1,2,8,3.5"]).unwrap();
+        assert_eq!(args.start_region, Some((Vector2::new(-1., 2.), Vector2::new(8., 3.5))));
+        assert!(args.world().is_ok());
+        assert!(Args::try_parse_from(["senter", "--start-region", "8,2,-1,3.5"]).is_err());
+        // Only the outer wall of the small map is in this box.
+        let err = Args::try_parse_from(["senter", "--start-region", "0,0,0.9,20"]).unwrap().world().unwrap_err();
+        assert!(err.starts_with("the start region has no free cell"), "{}", err);
+
+        let walls = parse_wall_points("# a wall\n0,0\n\n 1 , 2\n").unwrap();
+        assert_eq!(walls, vec![Vector2::new(0., 0.), Vector2::new(1., 2.)]);
+        assert!(parse_wall_points("0,0\n1\n").unwrap_err().starts_with("line 2"));
+    }
+}
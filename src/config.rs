@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use nalgebra::Vector2;
+use serde::Deserialize;
+
+use crate::grid::BeamCone;
+use crate::hardware::PinMap;
+use crate::localization::RangeModel;
+use crate::robo::sim_pi::NoiseConfig;
+use crate::robo::{DiffDrive, OdometryNoise};
+
+// Settings of one physical robot, read from a TOML or YAML file at startup. Everything that is
+// left out keeps the default, so a file only needs what differs on that robot.
+// Lengths are in map units (cells), angles in rad and wheel velocities in rad/s.
+//
+//   name = "lab robot 2"
+//   [geometry]
+//   wheel_base = 0.45
+//   sensor_offset = [0.2, 0.0]
+//   [velocity]
+//   max = 8.0
+//   [noise.sensor]
+//   p_short = 0.1
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RobotConfig {
+    pub name: String,
+    pub geometry: Geometry,
+    // Replaces the wiring of hardware::PINS_40. All of the pins have to be given.
+    pub pins: Option<PinMap>,
+    pub velocity: VelocityLimits,
+    pub sensor: SensorConfig,
+    pub noise: Noise,
+    pub filter: FilterConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Geometry {
+    pub wheel_base: f64,
+    pub wheel_radius: f64,
+    // Radius of the round footprint, for the collisions in the simulation.
+    pub robot_radius: f64,
+    // Position of the sensor relative to the center between the wheels, x forward and y left.
+    pub sensor_offset: [f64; 2],
+    // Direction the sensor looks in, relative to the heading. Positive is to the left.
+    pub sensor_angle: f64,
+}
+
+impl Default for Geometry {
+    fn default() -> Self {
+        let drive = DiffDrive::default();
+        Geometry { wheel_base: drive.wheel_base, wheel_radius: drive.wheel_radius, robot_radius: 0.3, sensor_offset: [0., 0.], sensor_angle: 0. }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VelocityLimits {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for VelocityLimits {
+    fn default() -> Self {
+        VelocityLimits { min: -10., max: 10. }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensorConfig {
+    // None keeps the default of the robot or the simulation. The command line wins over both.
+    pub max_range: Option<f64>,
+    // Opening angle of the beam and the number of rays to sweep it with.
+    pub beam_width: f64,
+    pub beam_rays: usize,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        let beam = BeamCone::default();
+        SensorConfig { max_range: None, beam_width: beam.width, beam_rays: beam.rays }
+    }
+}
+
+// Noise of the simulated sensor and of the odometry. The localizers take their range model
+// from the sensor noise and their motion model from the odometry noise, on the robot too.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Noise {
+    pub sensor: NoiseConfig,
+    pub odometry: OdometryNoise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    // Particle count without adaptive sampling.
+    pub particle_count: usize,
+    // KLD sampling and random particles, for global localization and a kidnapped robot.
+    pub adaptive: bool,
+    // None keeps the default of the histogram filter.
+    pub heading_bins: Option<usize>,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        FilterConfig { particle_count: crate::PARTICLE_COUNT, adaptive: false, heading_bins: None }
+    }
+}
+
+impl Default for RobotConfig {
+    fn default() -> Self {
+        RobotConfig {
+            name: "default".to_string(),
+            geometry: Geometry::default(),
+            pins: None,
+            velocity: VelocityLimits::default(),
+            sensor: SensorConfig::default(),
+            noise: Noise::default(),
+            filter: FilterConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(String, std::io::Error),
+    // The file is no valid TOML or YAML or has fields of the wrong type.
+    Parse(String, String),
+    // The file parsed but the values make no sense. Holds every problem, not only the first.
+    Invalid(String, Vec<String>),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "can't read config {}: {}", path, e),
+            ConfigError::Parse(path, e) => write!(f, "config {}: {}", path, e),
+            ConfigError::Invalid(path, problems) => {
+                write!(f, "config {} is not valid:", path)?;
+                for p in problems {
+                    write!(f, "\n  - {}", p)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl RobotConfig {
+    // Reads and validates the file. The format comes from the extension.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let name = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Read(name.clone(), e))?;
+        let config = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => RobotConfig::from_toml(&text),
+            Some("yaml") | Some("yml") => RobotConfig::from_yaml(&text),
+            _ => Err("unknown format, use .toml, .yaml or .yml".to_string()),
+        }.map_err(|e| ConfigError::Parse(name.clone(), e))?;
+        config.validate().map_err(|problems| ConfigError::Invalid(name, problems))?;
+        Ok(config)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    pub fn from_yaml(text: &str) -> Result<Self, String> {
+        serde_yaml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    // All problems of the config, so they can be fixed in one go.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: String| if !ok { problems.push(problem) };
+        let positive = |v: f64| v.is_finite() && v > 0.;
+        let non_negative = |v: f64| v.is_finite() && v >= 0.;
+        let probability = |v: f64| (0. ..=1.).contains(&v);
+
+        let g = &self.geometry;
+        check(positive(g.wheel_base), format!("geometry.wheel_base must be positive, is {}", g.wheel_base));
+        check(positive(g.wheel_radius), format!("geometry.wheel_radius must be positive, is {}", g.wheel_radius));
+        check(positive(g.robot_radius), format!("geometry.robot_radius must be positive, is {}", g.robot_radius));
+        check(g.sensor_offset.iter().all(|v| v.is_finite()), "geometry.sensor_offset must be finite".to_string());
+        check(g.sensor_angle.is_finite(), "geometry.sensor_angle must be finite".to_string());
+
+        let v = &self.velocity;
+        check(v.min.is_finite() && v.min <= 0., format!("velocity.min must be zero or negative, is {}", v.min));
+        check(positive(v.max), format!("velocity.max must be positive, is {}", v.max));
+
+        let s = &self.sensor;
+        if let Some(r) = s.max_range {
+            check(positive(r), format!("sensor.max_range must be positive, is {}", r));
+        }
+        check(non_negative(s.beam_width) && s.beam_width < std::f64::consts::PI, format!("sensor.beam_width must be in [0, pi), is {}", s.beam_width));
+        check(s.beam_rays >= 1, "sensor.beam_rays must be at least 1".to_string());
+
+        let n = &self.noise.sensor;
+        check(non_negative(n.sigma_hit), format!("noise.sensor.sigma_hit must not be negative, is {}", n.sigma_hit));
+        check(non_negative(n.sigma_hit_per_m), format!("noise.sensor.sigma_hit_per_m must not be negative, is {}", n.sigma_hit_per_m));
+        check(positive(n.lambda_short), format!("noise.sensor.lambda_short must be positive, is {}", n.lambda_short));
+        for (name, p) in [("p_short", n.p_short), ("p_max", n.p_max), ("p_rand", n.p_rand), ("p_specular", n.p_specular)] {
+            check(probability(p), format!("noise.sensor.{} must be in [0, 1], is {}", name, p));
+        }
+        check(n.p_short + n.p_max + n.p_rand <= 1., "noise.sensor.p_short + p_max + p_rand must not be above 1".to_string());
+        let o = &self.noise.odometry;
+        for (name, a) in [("alpha1", o.alpha1), ("alpha2", o.alpha2), ("alpha3", o.alpha3), ("alpha4", o.alpha4)] {
+            check(non_negative(a), format!("noise.odometry.{} must not be negative, is {}", name, a));
+        }
+
+        let f = &self.filter;
+        check(f.particle_count >= 1, "filter.particle_count must be at least 1".to_string());
+        if let Some(b) = f.heading_bins {
+            check(b >= 1, "filter.heading_bins must be at least 1".to_string());
+        }
+
+        if let Some(p) = &self.pins {
+            let mut used = vec![p.led, p.us_trig, p.us_echo, p.motor_left_pwm, p.motor_left_in1, p.motor_left_in2,
+                p.motor_right_pwm, p.motor_right_in1, p.motor_right_in2, p.encoder_left_a, p.encoder_right_a];
+            used.extend([p.motor_standby, p.encoder_left_b, p.encoder_right_b].into_iter().flatten());
+            for (i, pin) in used.iter().enumerate() {
+                check(*pin <= 27, format!("pins: GPIO {} is not on the header", pin));
+                check(!used[..i].contains(pin), format!("pins: GPIO {} is used twice", pin));
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(problems),
+        }
+    }
+
+    pub fn drive(&self) -> DiffDrive {
+        DiffDrive { wheel_base: self.geometry.wheel_base, wheel_radius: self.geometry.wheel_radius }
+    }
+
+    pub fn beam(&self) -> BeamCone {
+        BeamCone {
+            width: self.sensor.beam_width,
+            rays: self.sensor.beam_rays,
+            offset: Vector2::from(self.geometry.sensor_offset),
+            mount_angle: self.geometry.sensor_angle,
+        }
+    }
+
+    // Range model of the localizers, fitted to the sensor noise. Short readings don't fit the
+    // map either, so they count as random ones. Specular reflections depend on the angle the
+    // localizers don't know, so p_miss doesn't drop below the default.
+    pub fn range_model(&self) -> RangeModel {
+        let n = &self.noise.sensor;
+        let default = RangeModel::default();
+        RangeModel {
+            sigma_hit: n.sigma_hit,
+            sigma_hit_per_m: n.sigma_hit_per_m,
+            min_sigma: default.min_sigma,
+            p_rand: (n.p_rand + n.p_short).min(1.),
+            p_miss: n.p_max.max(default.p_miss),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_and_partial_files() {
+        assert_eq!(RobotConfig::from_toml("").unwrap(), RobotConfig::default());
+        assert!(RobotConfig::default().validate().is_ok());
+        // The fixed particle count is the default, adaptive sampling has to be asked for.
+        assert!(!RobotConfig::default().filter.adaptive);
+        assert!(RobotConfig::from_toml("[filter]\nadaptive = true\n").unwrap().filter.adaptive);
+
+        let toml = "name = \"r2\"\n[geometry]\nwheel_base = 0.4\nsensor_offset = [0.2, 0.1]\n[noise.sensor]\np_short = 0.1\n";
+        let c = RobotConfig::from_toml(toml).unwrap();
+        assert_eq!((c.name.as_str(), c.geometry.wheel_base, c.geometry.wheel_radius), ("r2", 0.4, 0.1));
+        assert_eq!(c.beam().offset, Vector2::new(0.2, 0.1));
+        assert_eq!(c.noise.sensor.p_short, 0.1);
+        assert!((c.range_model().p_rand - 0.1 - c.noise.sensor.p_rand).abs() < 1e-12);
+        let yaml = "name: r2\ngeometry:\n  wheel_base: 0.4\n  sensor_offset: [0.2, 0.1]\nnoise:\n  sensor:\n    p_short: 0.1\n";
+        assert_eq!(RobotConfig::from_yaml(yaml).unwrap(), c);
+
+        // Typos are errors, not silently ignored.
+        assert!(RobotConfig::from_toml("[geometry]\nwheelbase = 0.4\n").is_err());
+    }
+
+    #[test]
+    fn validation_lists_every_problem() {
+        let toml = "[geometry]\nwheel_radius = -0.1\n[velocity]\nmin = 1.0\n[noise.sensor]\np_max = 1.5\n";
+        let problems = RobotConfig::from_toml(toml).unwrap().validate().unwrap_err();
+        assert_eq!(problems.len(), 4, "{:?}", problems);
+        assert!(problems[0].starts_with("geometry.wheel_radius"));
+
+        let mut c = RobotConfig { pins: Some(crate::hardware::PINS_40), ..RobotConfig::default() };
+        assert!(c.validate().is_ok());
+        c.pins.as_mut().unwrap().led = c.pins.unwrap().us_trig;
+        assert_eq!(c.validate().unwrap_err(), vec![format!("pins: GPIO {} is used twice", c.pins.unwrap().led)]);
+    }
+}
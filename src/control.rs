@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+// Closed loop wheel speed control. Every wheel has its own PID controller that turns the
+// velocity error into a duty cycle. The loop runs at a fixed rate on its own thread, against the
+// real motors and encoders or against the motor model of the simulation.
+
+// Gains of one wheel controller. The output is a duty cycle in -1..=1, velocities are in rad/s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PidGains {
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    // Feed-forward, duty per rad/s of setpoint. About 1 / top speed of the motor.
+    pub kff: f64,
+}
+
+impl Default for PidGains {
+    // Tuned against the default MotorModel.
+    fn default() -> Self {
+        PidGains { kp: 0.05, ki: 0.5, kd: 0., kff: 1. / 12. }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pid {
+    pub gains: PidGains,
+    // Already multiplied with ki, so changing the gains doesn't make the output jump.
+    integral: f64,
+    last_error: Option<f64>,
+}
+
+impl Pid {
+    pub fn new(gains: PidGains) -> Self {
+        Pid { gains, integral: 0., last_error: None }
+    }
+
+    pub fn reset(&mut self) {
+        self.integral = 0.;
+        self.last_error = None;
+    }
+
+    // Duty cycle for the next dt seconds. A setpoint of 0 stops the wheel right away and clears
+    // the integral, so the robot doesn't creep when it should stand still.
+    // Anti-windup by conditional integration: while the output is saturated the integral only
+    // moves in the direction that gets it out of saturation.
+    pub fn update(&mut self, setpoint: f64, measured: f64, dt: f64) -> f64 {
+        if setpoint == 0. {
+            self.reset();
+            return 0.;
+        }
+        let g = self.gains;
+        let error = setpoint - measured;
+        let derivative = match (self.last_error, dt > 0.) {
+            (Some(last), true) => (error - last) / dt,
+            _ => 0.,
+        };
+        self.last_error = Some(error);
+        let without_i = g.kff * setpoint + g.kp * error + g.kd * derivative;
+        let integral = self.integral + g.ki * error * dt;
+        let out = without_i + integral;
+        if out.abs() <= 1. || out.signum() != error.signum() {
+            self.integral = integral;
+        }
+        (without_i + self.integral).clamp(-1., 1.)
+    }
+}
+
+// The motors and the wheel sensors the controller runs against.
+pub trait WheelPlant: Send {
+    // Sets the duty cycles (-1..=1) of the left and right motor.
+    fn apply(&mut self, left: f64, right: f64);
+    // Lets dt seconds pass and returns the rotation of the wheels in rad since the last call.
+    // Real hardware ignores dt, time passes by itself there.
+    fn advance(&mut self, dt: f64) -> (f64, f64);
+}
+
+// One DC motor with its wheel: a first order lag towards the speed the duty cycle asks for.
+// Below the friction duty the motor doesn't move at all.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotorParams {
+    // Speed at full duty in rad/s.
+    pub max_velo: f64,
+    // Time constant of the lag in s.
+    pub time_constant: f64,
+    pub friction: f64,
+}
+
+impl Default for MotorParams {
+    fn default() -> Self {
+        MotorParams { max_velo: 12., time_constant: 0.1, friction: 0.1 }
+    }
+}
+
+impl MotorParams {
+    // Speed the motor runs at in steady state for the duty cycle.
+    fn steady_velo(&self, duty: f64) -> f64 {
+        let d = duty.clamp(-1., 1.);
+        if d.abs() <= self.friction {
+            return 0.;
+        }
+        self.max_velo * d.signum() * (d.abs() - self.friction) / (1. - self.friction)
+    }
+}
+
+// Simulated motors for offline tuning and the simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotorModel {
+    pub left: MotorParams,
+    pub right: MotorParams,
+    duty: (f64, f64),
+    velo: (f64, f64),
+}
+
+impl Default for MotorModel {
+    fn default() -> Self {
+        MotorModel::new(MotorParams::default(), MotorParams::default())
+    }
+}
+
+impl MotorModel {
+    pub fn new(left: MotorParams, right: MotorParams) -> Self {
+        MotorModel { left, right, duty: (0., 0.), velo: (0., 0.) }
+    }
+}
+
+// Exact solution of the lag over dt. Returns the new speed and the angle turned.
+fn lag(p: &MotorParams, velo: f64, duty: f64, dt: f64) -> (f64, f64) {
+    let target = p.steady_velo(duty);
+    if p.time_constant <= 0. {
+        return (target, target * dt);
+    }
+    let decay = (-dt / p.time_constant).exp();
+    (target + (velo - target) * decay, target * dt + (velo - target) * p.time_constant * (1. - decay))
+}
+
+impl WheelPlant for MotorModel {
+    fn apply(&mut self, left: f64, right: f64) {
+        self.duty = (left, right);
+    }
+
+    fn advance(&mut self, dt: f64) -> (f64, f64) {
+        let (vl, al) = lag(&self.left, self.velo.0, self.duty.0, dt);
+        let (vr, ar) = lag(&self.right, self.velo.1, self.duty.1, dt);
+        self.velo = (vl, vr);
+        (al, ar)
+    }
+}
+
+// One controller per wheel and the plant they drive. tick is the whole control step, so the
+// loop can run on a thread or be stepped by hand for tuning.
+#[derive(Debug)]
+pub struct ControlLoop<P: WheelPlant> {
+    pub plant: P,
+    left: Pid,
+    right: Pid,
+    setpoint: (f64, f64),
+    measured: (f64, f64),
+}
+
+impl<P: WheelPlant> ControlLoop<P> {
+    pub fn new(plant: P, gains: PidGains) -> Self {
+        ControlLoop { plant, left: Pid::new(gains), right: Pid::new(gains), setpoint: (0., 0.), measured: (0., 0.) }
+    }
+
+    pub fn set_setpoint(&mut self, left: f64, right: f64) {
+        self.setpoint = (left, right);
+    }
+
+    // Measures the wheel speeds over the last dt seconds and sets the new duty cycles.
+    // Returns the wheel rotation in rad since the last tick.
+    pub fn tick(&mut self, dt: f64) -> (f64, f64) {
+        let (al, ar) = self.plant.advance(dt);
+        if dt > 0. {
+            self.measured = (al / dt, ar / dt);
+        }
+        let dl = self.left.update(self.setpoint.0, self.measured.0, dt);
+        let dr = self.right.update(self.setpoint.1, self.measured.1, dt);
+        self.plant.apply(dl, dr);
+        (al, ar)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    setpoint: (f64, f64),
+    // Wheel rotation since the last take_angles.
+    angles: (f64, f64),
+}
+
+// Runs a ControlLoop at a fixed rate on its own thread. Dropping it stops the motors.
+#[derive(Debug)]
+pub struct SpeedController {
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SpeedController {
+    pub fn spawn<P: WheelPlant + 'static>(plant: P, gains: PidGains, rate_hz: f64) -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let period = Duration::from_secs_f64(1. / rate_hz.max(1.));
+        let (s, st) = (shared.clone(), stop.clone());
+        let handle = std::thread::spawn(move || {
+            let mut control = ControlLoop::new(plant, gains);
+            let mut last = Instant::now();
+            let mut next = last + period;
+            while !st.load(Ordering::Relaxed) {
+                std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                next += period;
+                // Use the real time that passed, the sleep is never exact.
+                let dt = last.elapsed().as_secs_f64();
+                last = Instant::now();
+                let setpoint = s.lock().unwrap().setpoint;
+                control.set_setpoint(setpoint.0, setpoint.1);
+                let (al, ar) = control.tick(dt);
+                let mut sh = s.lock().unwrap();
+                sh.angles.0 += al;
+                sh.angles.1 += ar;
+            }
+            control.plant.apply(0., 0.);
+        });
+        SpeedController { shared, stop, handle: Some(handle) }
+    }
+
+    pub fn set_setpoint(&self, left: f64, right: f64) {
+        self.shared.lock().unwrap().setpoint = (left, right);
+    }
+
+    // Wheel rotation in rad since the last call.
+    pub fn take_angles(&self) -> (f64, f64) {
+        std::mem::take(&mut self.shared.lock().unwrap().angles)
+    }
+}
+
+impl Drop for SpeedController {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_setpoint_on_motor_model() {
+        // The right motor is weaker, the integral has to make up for it.
+        let weak = MotorParams { max_velo: 9., ..MotorParams::default() };
+        let mut c = ControlLoop::new(MotorModel::new(MotorParams::default(), weak), PidGains::default());
+        c.set_setpoint(6., 6.);
+        for _ in 0..300 {
+            c.tick(0.01);
+        }
+        let (l, r) = c.measured;
+        assert!((l - 6.).abs() < 0.1 && (r - 6.).abs() < 0.1, "{l} {r}");
+    }
+
+    #[test]
+    fn integral_does_not_wind_up() {
+        let mut c = ControlLoop::new(MotorModel::default(), PidGains::default());
+        // Faster than the motor can go, the output saturates for a long time.
+        c.set_setpoint(20., 20.);
+        for _ in 0..500 {
+            c.tick(0.01);
+        }
+        c.set_setpoint(5., 5.);
+        for _ in 0..50 {
+            c.tick(0.01);
+        }
+        assert!((c.measured.0 - 5.).abs() < 0.5, "{:?}", c.measured);
+        // Zero stops right away.
+        c.set_setpoint(0., 0.);
+        c.tick(0.01);
+        assert_eq!(c.plant.duty, (0., 0.));
+    }
+}
@@ -0,0 +1,111 @@
+use std::f64::consts::TAU;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+use rppal::gpio::{Gpio, InputPin, Trigger};
+
+// Wheel encoders read with GPIO interrupts. The interrupt thread of rppal only counts ticks,
+// everything else happens when the robot steps.
+//
+// With a quadrature encoder (two channels A and B) we count both edges of A and look at B to
+// know the direction. A single channel encoder (slotted disk and a light barrier) can't tell
+// the direction, so we take the one the motor is driven in.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncoderConfig {
+    pub a: u8,
+    // None for a single channel encoder.
+    pub b: Option<u8>,
+    // Counted edges per wheel revolution. Both edges of A are counted, so this is twice the
+    // number of slots or lines on the disk (times the gear ratio if it sits on the motor).
+    pub ticks_per_rev: f64,
+    // Swaps forward and backward, for the wheel that is mounted mirrored.
+    pub invert: bool,
+}
+
+// Direction of one tick from an edge of A and the level of B.
+// Forward, B lags A: on a rising A the B channel is still low, on a falling A it is high.
+pub fn quadrature_step(a_rising: bool, b_high: bool) -> i64 {
+    match a_rising == b_high {
+        true => -1,
+        false => 1,
+    }
+}
+
+#[derive(Debug)]
+pub struct Encoder {
+    // Needs to stay alive, dropping the pin clears the interrupt.
+    _pin_a: InputPin,
+    count: Arc<AtomicI64>,
+    // Sign of the ticks of a single channel encoder, set from the driven direction.
+    direction: Arc<AtomicI64>,
+    last_count: i64,
+    ticks_per_rev: f64,
+    invert: bool,
+}
+
+impl Encoder {
+    pub fn new(gpio: &Gpio, config: EncoderConfig) -> Result<Self, rppal::gpio::Error> {
+        let count = Arc::new(AtomicI64::new(0));
+        let direction = Arc::new(AtomicI64::new(1));
+        let mut pin_a = gpio.get(config.a)?.into_input_pullup();
+        match config.b {
+            Some(b) => {
+                let pin_b = gpio.get(b)?.into_input_pullup();
+                let c = count.clone();
+                pin_a.set_async_interrupt(Trigger::Both, None, move |event| {
+                    let step = quadrature_step(event.trigger == Trigger::RisingEdge, pin_b.is_high());
+                    c.fetch_add(step, Ordering::Relaxed);
+                })?;
+            },
+            None => {
+                let (c, d) = (count.clone(), direction.clone());
+                pin_a.set_async_interrupt(Trigger::Both, None, move |_| {
+                    c.fetch_add(d.load(Ordering::Relaxed), Ordering::Relaxed);
+                })?;
+            },
+        }
+        Ok(Encoder {
+            _pin_a: pin_a,
+            count,
+            direction,
+            last_count: 0,
+            ticks_per_rev: config.ticks_per_rev,
+            invert: config.invert,
+        })
+    }
+
+    // Only used by single channel encoders, quadrature encoders know the direction themselves.
+    pub fn set_direction(&self, forward: bool) {
+        let sign = if forward != self.invert { 1 } else { -1 };
+        self.direction.store(sign, Ordering::Relaxed);
+    }
+
+    // Wheel rotation in rad since the last call.
+    pub fn take_angle(&mut self) -> f64 {
+        let count = self.count.load(Ordering::Relaxed);
+        let ticks = count - self.last_count;
+        self.last_count = count;
+        let angle = ticks_to_angle(ticks, self.ticks_per_rev);
+        if self.invert { -angle } else { angle }
+    }
+}
+
+pub fn ticks_to_angle(ticks: i64, ticks_per_rev: f64) -> f64 {
+    ticks as f64 * TAU / ticks_per_rev
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quadrature_counts_both_directions() {
+        // Forward: A rises (B low), B rises, A falls (B high), B falls.
+        let forward = [quadrature_step(true, false), quadrature_step(false, true)];
+        assert_eq!(forward, [1, 1]);
+        let backward = [quadrature_step(true, true), quadrature_step(false, false)];
+        assert_eq!(backward, [-1, -1]);
+        assert!((ticks_to_angle(20, 40.) - std::f64::consts::PI).abs() < 1e-12);
+    }
+}
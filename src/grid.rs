@@ -0,0 +1,295 @@
+use nalgebra::{Vector2, Vector3};
+
+// Occupancy grid of the world. Used by the simulation to fake the sensor and by the
+// localization to know what the sensor should see.
+
+#[derive(Debug,Clone,Copy, PartialEq, Eq)]
+pub enum PositionType {
+    Wall,
+    Empty,
+}
+
+// Opening angle of the ultrasonic beam. The HC-SR04 reports the nearest echo
+// anywhere inside the cone, so we sweep rays evenly across it and keep the shortest hit.
+// The sensor sits at offset from the center of the robot (x forward, y left) and looks
+// mount_angle to the left of the heading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeamCone {
+    // Full width of the cone in rad.
+    pub width: f64,
+    // Number of rays to sweep. 1 means only the center line.
+    pub rays: usize,
+    pub offset: Vector2<f64>,
+    pub mount_angle: f64,
+}
+
+impl Default for BeamCone {
+    // About 20 degree, a bit narrower than the data sheet's 30 degree.
+    fn default() -> Self {
+        BeamCone { width: 20_f64.to_radians(), rays: 7, offset: Vector2::zeros(), mount_angle: 0. }
+    }
+}
+
+impl BeamCone {
+    // Pose of the sensor in the map for the robot at pose.
+    pub fn sensor_pose(&self, pose: Vector3<f64>) -> Vector3<f64> {
+        let (s, c) = pose.z.sin_cos();
+        Vector3::new(
+            pose.x + c * self.offset.x - s * self.offset.y,
+            pose.y + s * self.offset.x + c * self.offset.y,
+            pose.z + self.mount_angle,
+        )
+    }
+
+    // The ray angles relative to the center line of the beam.
+    pub fn ray_offsets(&self) -> Vec<f64> {
+        if self.rays <= 1 {
+            return vec![0.];
+        }
+        let step = self.width / (self.rays - 1) as f64;
+        (0..self.rays)
+            .map(|i| -self.width / 2. + step * i as f64)
+            .collect()
+    }
+}
+
+// Result of a ray cast. incidence is the angle between the ray and the normal of the
+// wall face that was hit (0 = head on).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub dist: f64,
+    pub incidence: f64,
+}
+
+// Grid of cells with size 1, indexed as cells[x][y] relative to origin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridMap {
+    cells: Vec<Vec<PositionType>>,
+    origin: Vector2<f64>,
+}
+
+impl GridMap {
+    // Builds the grid from a list of wall points. Every point marks the cell it lies in.
+    // The grid spans the bounding box of the points, both ends included.
+    pub fn from_points(points: &[Vector2<f64>]) -> Self {
+        if points.is_empty() {
+            return GridMap { cells: Vec::new(), origin: Vector2::new(0., 0.) };
+        }
+        let (minx,miny,maxx,maxy) = points.iter()
+            .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |mut acc, v| {
+                acc.0 = acc.0.min(v.x.floor());
+                acc.1 = acc.1.min(v.y.floor());
+                acc.2 = acc.2.max(v.x.floor());
+                acc.3 = acc.3.max(v.y.floor());
+                acc
+        });
+        let col = vec![PositionType::Empty; (maxy - miny) as usize + 1];
+        let mut cells = vec![col; (maxx - minx) as usize + 1];
+        for v in points {
+            cells[(v.x.floor() - minx) as usize][(v.y.floor() - miny) as usize] = PositionType::Wall;
+        }
+        GridMap { cells, origin: Vector2::new(minx, miny) }
+    }
+
+    // Returns the lower left corner of every wall cell, sorted by x and then y.
+    pub fn to_points(&self) -> Vec<Vector2<f64>> {
+        let mut v = Vec::new();
+        for (i, col) in self.cells.iter().enumerate() {
+            for (j, cell) in col.iter().enumerate() {
+                if *cell == PositionType::Wall {
+                    v.push(self.origin + Vector2::new(i as f64, j as f64));
+                }
+            }
+        }
+        v
+    }
+
+    // Lower left corner of cell (0, 0).
+    pub fn origin(&self) -> Vector2<f64> {
+        self.origin
+    }
+
+    // Number of cells along x.
+    pub fn width(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Number of cells along y.
+    pub fn height(&self) -> usize {
+        self.cells.first().map_or(0, |c| c.len())
+    }
+
+    // True for the map of an empty wall list, it has no cells at all.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> PositionType {
+        self.cells[i][j]
+    }
+
+    pub fn cells(&self) -> &Vec<Vec<PositionType>> {
+        &self.cells
+    }
+
+    // Index of the cell that contains the point (x, y). None if it lies outside of the map.
+    pub fn cell_index(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        let i = (x - self.origin.x).floor();
+        let j = (y - self.origin.y).floor();
+        if i < 0. || j < 0. || i as usize >= self.width() || j as usize >= self.height() {
+            return None;
+        }
+        Some((i as usize, j as usize))
+    }
+
+    // Center of cell (i, j) in map coordinates.
+    pub fn cell_center(&self, i: usize, j: usize) -> Vector2<f64> {
+        self.origin + Vector2::new(i as f64 + 0.5, j as f64 + 0.5)
+    }
+
+    // Returns the type of the cell that contains the point (x, y).
+    // None if the point lies outside of the map.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<PositionType> {
+        self.cell_index(x, y).map(|(i, j)| self.cells[i][j])
+    }
+
+    pub fn is_free(&self, x: f64, y: f64) -> bool {
+        self.cell_at(x, y) == Some(PositionType::Empty)
+    }
+
+    // Returns the first wall cell that overlaps a round footprint with radius r at position p.
+    // Cells outside of the map count as walls, so the robot can't leave the map.
+    pub fn footprint_collision(&self, p: Vector2<f64>, r: f64) -> Option<Vector2<f64>> {
+        let (x0, x1) = ((p.x - r).floor() as i64, (p.x + r).floor() as i64);
+        let (y0, y1) = ((p.y - r).floor() as i64, (p.y + r).floor() as i64);
+        for i in x0..=x1 {
+            for j in y0..=y1 {
+                let (cx, cy) = (i as f64, j as f64);
+                if self.is_free(cx + 0.5, cy + 0.5) {
+                    continue;
+                }
+                // Closest point of the cell to the center of the robot.
+                let closest = Vector2::new(p.x.clamp(cx, cx + 1.), p.y.clamp(cy, cy + 1.));
+                if (closest - p).norm() < r {
+                    return Some(Vector2::new(cx, cy));
+                }
+            }
+        }
+        None
+    }
+
+    // Indices of all free cells.
+    pub fn free_cells(&self) -> Vec<(usize, usize)> {
+        let mut v = Vec::new();
+        for (i, col) in self.cells.iter().enumerate() {
+            for (j, cell) in col.iter().enumerate() {
+                if *cell == PositionType::Empty {
+                    v.push((i, j));
+                }
+            }
+        }
+        v
+    }
+
+    // Casts a ray from origin in direction angle (rad) through the grid and returns
+    // the first wall cell hit. None if no wall is hit within max_range or the ray leaves the map.
+    // This walks the grid cell by cell (Amanatides & Woo), so thin walls are never skipped.
+    pub fn cast_ray(&self, origin: Vector2<f64>, angle: f64, max_range: f64) -> Option<RayHit> {
+        match self.cell_at(origin.x, origin.y) {
+            Some(PositionType::Wall) => return Some(RayHit { dist: 0., incidence: 0. }),
+            Some(PositionType::Empty) => (),
+            None => return None,
+        }
+        let dir = Vector2::new(angle.cos(), angle.sin());
+        let mut cell_x = origin.x.floor();
+        let mut cell_y = origin.y.floor();
+        // Step direction and distance along the ray between two grid lines for each axis.
+        let step_x = dir.x.signum();
+        let step_y = dir.y.signum();
+        let delta_x = if dir.x != 0. { 1. / dir.x.abs() } else { f64::INFINITY };
+        let delta_y = if dir.y != 0. { 1. / dir.y.abs() } else { f64::INFINITY };
+        // Distance along the ray to the next vertical and horizontal grid line. A ray parallel to
+        // an axis never crosses its lines, and 0 * infinity would be NaN on a grid line.
+        let mut next_x = if dir.x > 0. {
+            (cell_x + 1. - origin.x) * delta_x
+        } else if dir.x < 0. {
+            (origin.x - cell_x) * delta_x
+        } else {
+            f64::INFINITY
+        };
+        let mut next_y = if dir.y > 0. {
+            (cell_y + 1. - origin.y) * delta_y
+        } else if dir.y < 0. {
+            (origin.y - cell_y) * delta_y
+        } else {
+            f64::INFINITY
+        };
+        loop {
+            // The angle of incidence depends on whether we crossed a vertical or a horizontal face.
+            let (t, incidence) = if next_x < next_y {
+                cell_x += step_x;
+                let t = next_x;
+                next_x += delta_x;
+                (t, dir.x.abs().min(1.).acos())
+            } else {
+                cell_y += step_y;
+                let t = next_y;
+                next_y += delta_y;
+                (t, dir.y.abs().min(1.).acos())
+            };
+            if t > max_range {
+                return None;
+            }
+            // Sample the middle of the cell so we don't land on a grid line.
+            match self.cell_at(cell_x + 0.5, cell_y + 0.5) {
+                Some(PositionType::Wall) => return Some(RayHit { dist: t, incidence }),
+                Some(PositionType::Empty) => (),
+                None => return None,
+            }
+        }
+    }
+
+    // Sweeps the beam cone of the sensor on a robot at pose and returns the nearest hit.
+    pub fn cone_hit(&self, pose: Vector3<f64>, beam: &BeamCone, max_range: f64) -> Option<RayHit> {
+        let sensor = beam.sensor_pose(pose);
+        beam.ray_offsets()
+            .into_iter()
+            .filter_map(|offset| self.cast_ray(Vector2::new(sensor.x, sensor.y), sensor.z + offset, max_range))
+            .min_by(|a, b| a.dist.total_cmp(&b.dist))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 5 x 5 box, the free cells are 1..=3 in x and y.
+    fn small_box() -> GridMap {
+        let mut walls = Vec::new();
+        for i in 0..5 {
+            walls.extend([Vector2::new(i as f64, 0.), Vector2::new(i as f64, 4.), Vector2::new(0., i as f64), Vector2::new(4., i as f64)]);
+        }
+        GridMap::from_points(&walls)
+    }
+
+    #[test]
+    fn rays_along_the_axes_from_a_grid_line() {
+        let map = small_box();
+        let origin = Vector2::new(2., 2.);
+        let along_x = map.cast_ray(origin, 0., 10.).unwrap();
+        assert_eq!((along_x.dist, along_x.incidence), (2., 0.));
+        let along_y = map.cast_ray(origin, std::f64::consts::FRAC_PI_2, 10.).unwrap();
+        assert!((along_y.dist - 2.).abs() < 1e-9, "{:?}", along_y);
+        assert_eq!(map.cast_ray(origin, std::f64::consts::PI, 10.).map(|h| h.dist), Some(1.));
+        assert_eq!(map.cast_ray(origin, 0., 1.5), None);
+    }
+
+    #[test]
+    fn footprint_reaches_into_the_wall() {
+        let map = small_box();
+        assert_eq!(map.footprint_collision(Vector2::new(2.5, 2.5), 0.3), None);
+        // The center is free, but the radius reaches the wall on the left.
+        assert_eq!(map.footprint_collision(Vector2::new(1.2, 2.5), 0.3), Some(Vector2::new(0., 2.)));
+        assert_eq!(map.footprint_collision(Vector2::new(1.35, 2.5), 0.3), None);
+    }
+}
@@ -0,0 +1,114 @@
+use rppal::pwm::Channel;
+use rppal::system::Model;
+use serde::Deserialize;
+
+// What the different Raspberry Pi models can do. main looks up the profile of the board it
+// runs on and sets up the robot with it.
+
+// GPIO pins (BCM numbers) of everything on the robot.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PinMap {
+    pub led: u8,
+    pub us_trig: u8,
+    pub us_echo: u8,
+    pub motor_left_pwm: u8,
+    pub motor_left_in1: u8,
+    pub motor_left_in2: u8,
+    pub motor_right_pwm: u8,
+    pub motor_right_in1: u8,
+    pub motor_right_in2: u8,
+    pub motor_standby: Option<u8>,
+    pub encoder_left_a: u8,
+    pub encoder_left_b: Option<u8>,
+    pub encoder_right_a: u8,
+    pub encoder_right_b: Option<u8>,
+}
+
+// The wiring of the robot on the 40 pin header, from the consts in main.
+pub const PINS_40: PinMap = PinMap {
+    led: crate::GPIO_LED,
+    us_trig: crate::GPIO_US_TRIG,
+    us_echo: crate::GPIO_US_ECHO,
+    motor_left_pwm: crate::GPIO_MOTOR_LEFT_PWM,
+    motor_left_in1: crate::GPIO_MOTOR_LEFT_IN1,
+    motor_left_in2: crate::GPIO_MOTOR_LEFT_IN2,
+    motor_right_pwm: crate::GPIO_MOTOR_RIGHT_PWM,
+    motor_right_in1: crate::GPIO_MOTOR_RIGHT_IN1,
+    motor_right_in2: crate::GPIO_MOTOR_RIGHT_IN2,
+    motor_standby: crate::GPIO_MOTOR_STANDBY,
+    encoder_left_a: crate::GPIO_ENCODER_LEFT_A,
+    encoder_left_b: crate::GPIO_ENCODER_LEFT_B,
+    encoder_right_a: crate::GPIO_ENCODER_RIGHT_A,
+    encoder_right_b: crate::GPIO_ENCODER_RIGHT_B,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HardwareProfile {
+    pub name: &'static str,
+    // Rate of the wheel speed control loop. The single core boards get a slower one.
+    pub control_rate: f64,
+}
+
+const fn slow(name: &'static str) -> HardwareProfile {
+    HardwareProfile { name, control_rate: 50. }
+}
+
+const fn fast(name: &'static str) -> HardwareProfile {
+    HardwareProfile { name, control_rate: 100. }
+}
+
+// All boards we know how to drive the robot with, all wired like PINS_40. The first models only
+// have the 26 pin header, the motor and encoder pins are not on it, so they are missing here.
+pub const PROFILES: &[(Model, HardwareProfile)] = &[
+    (Model::RaspberryPiAPlus, slow("Pi A+")),
+    (Model::RaspberryPiBPlus, slow("Pi B+")),
+    (Model::RaspberryPiZero, slow("Pi Zero")),
+    (Model::RaspberryPiZeroW, slow("Pi Zero W")),
+    (Model::RaspberryPi2B, fast("Pi 2 B")),
+    (Model::RaspberryPi3B, fast("Pi 3 B")),
+    (Model::RaspberryPi3BPlus, fast("Pi 3 B+")),
+    (Model::RaspberryPi3APlus, fast("Pi 3 A+")),
+    (Model::RaspberryPiZero2W, fast("Pi Zero 2 W")),
+    (Model::RaspberryPi4B, fast("Pi 4 B")),
+    (Model::RaspberryPi400, fast("Pi 400")),
+    (Model::RaspberryPi5, fast("Pi 5")),
+];
+
+// Hardware PWM channel of a GPIO pin. We set the pwm-2chan overlay to GPIO 12 and 13, every
+// other pin needs software PWM.
+pub fn pwm_channel(gpio: u8) -> Option<Channel> {
+    match gpio {
+        12 => Some(Channel::Pwm0),
+        13 => Some(Channel::Pwm1),
+        _ => None,
+    }
+}
+
+pub fn profile(model: Model) -> Option<&'static HardwareProfile> {
+    PROFILES.iter().find(|(m, _)| *m == model).map(|(_, p)| p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_and_unknown_models() {
+        assert_eq!(profile(Model::RaspberryPi4B).unwrap().name, "Pi 4 B");
+        assert_eq!(profile(Model::RaspberryPiZero).unwrap().control_rate, 50.);
+        assert!(profile(Model::RaspberryPi5).is_some());
+        assert!(profile(Model::RaspberryPiBRev1).is_none());
+        // No model shows up twice.
+        for (i, (m, _)) in PROFILES.iter().enumerate() {
+            assert!(PROFILES[i + 1..].iter().all(|(n, _)| n != m));
+        }
+    }
+
+    #[test]
+    fn only_the_overlay_pins_have_hardware_pwm() {
+        assert_eq!(pwm_channel(PINS_40.motor_left_pwm), Some(Channel::Pwm0));
+        assert_eq!(pwm_channel(PINS_40.motor_right_pwm), Some(Channel::Pwm1));
+        assert_eq!(pwm_channel(18), None);
+    }
+}
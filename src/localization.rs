@@ -0,0 +1,101 @@
+// Estimators that find out where the robot is. They only talk to the robot through
+// RobotAccess and the map, so they run on the simulation and on the real robot alike.
+
+use nalgebra::{Matrix3, Vector2, Vector3};
+
+use crate::robo::{normalize_angle, RobotStartBelief};
+
+pub mod ekf;
+#[cfg(test)]
+mod fixtures;
+pub mod histogram;
+pub mod metrics;
+pub mod particle;
+
+// Common interface of the estimators, so the app can switch between them at runtime.
+pub trait Localizer: std::fmt::Debug {
+    // Short name for the UI.
+    fn name(&self) -> &'static str;
+    // Forgets everything and starts over with the given belief.
+    fn reset(&mut self, start: &RobotStartBelief);
+    // Motion step with the wheel velocities that were driven for dt seconds.
+    fn predict(&mut self, left: f64, right: f64, dt: f64);
+    // Measurement step with one ultrasonic reading. None means no echo.
+    fn update(&mut self, measured: Option<f64>);
+    // Mean and covariance of the pose.
+    fn estimate(&self) -> (Vector3<f64>, Matrix3<f64>);
+    // Weighted positions to draw on the map (particles, grid cells, ...).
+    fn samples(&self) -> Vec<(Vector2<f64>, f64)>;
+}
+
+// Likelihood of an ultrasonic reading given the reading the map predicts. Mixture of a
+// gaussian around the expected range, random readings and missing echos.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeModel {
+    // Std deviation of the hit is sigma_hit + sigma_hit_per_m * distance, but at least min_sigma.
+    pub sigma_hit: f64,
+    pub sigma_hit_per_m: f64,
+    pub min_sigma: f64,
+    // Weight of readings that don't fit the map at all (people, cables, ...).
+    pub p_rand: f64,
+    // Likelihood of no echo if the map says there should be one.
+    pub p_miss: f64,
+}
+
+impl Default for RangeModel {
+    fn default() -> Self {
+        RangeModel { sigma_hit: 0.01, sigma_hit_per_m: 0.01, min_sigma: 0.1, p_rand: 0.05, p_miss: 0.1 }
+    }
+}
+
+impl RangeModel {
+    pub fn likelihood(&self, expected: Option<f64>, measured: Option<f64>, max_range: f64) -> f64 {
+        match (expected, measured) {
+            (Some(e), Some(z)) => {
+                let sigma = (self.sigma_hit + self.sigma_hit_per_m * e).max(self.min_sigma);
+                let g = (-0.5 * ((z - e) / sigma).powi(2)).exp() / (sigma * (2. * std::f64::consts::PI).sqrt());
+                (1. - self.p_rand) * g + self.p_rand / max_range
+            },
+            (None, Some(_)) => self.p_rand / max_range,
+            (Some(_), None) => self.p_miss,
+            (None, None) => 1.,
+        }
+    }
+}
+
+// Weighted mean and covariance of a list of poses. The heading uses the circular mean.
+pub fn weighted_moments(h: &[(Vector3<f64>, f64)]) -> (Vector3<f64>, Matrix3<f64>) {
+    let total: f64 = h.iter().map(|(_, w)| w.max(0.)).sum();
+    if total <= 0. {
+        return (Vector3::zeros(), Matrix3::identity());
+    }
+    let (mut x, mut y, mut s, mut c) = (0., 0., 0., 0.);
+    for (p, w) in h {
+        let w = w.max(0.) / total;
+        x += w * p.x;
+        y += w * p.y;
+        s += w * p.z.sin();
+        c += w * p.z.cos();
+    }
+    let mean = Vector3::new(x, y, s.atan2(c));
+    let mut cov = Matrix3::zeros();
+    for (p, w) in h {
+        let mut d = p - mean;
+        d.z = normalize_angle(d.z);
+        cov += w.max(0.) / total * d * d.transpose();
+    }
+    (mean, cov)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moments_of_two_poses() {
+        let (mean, cov) = weighted_moments(&[(Vector3::new(0., 0., 0.1), 1.), (Vector3::new(2., 0., -0.1), 1.)]);
+        assert!((mean - Vector3::new(1., 0., 0.)).norm() < 1e-9);
+        assert!((cov[(0, 0)] - 1.).abs() < 1e-9);
+        assert!((cov[(2, 2)] - 0.01).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,257 @@
+use nalgebra::{Matrix3, RowVector3, Vector2, Vector3};
+
+use super::{weighted_moments, Localizer, RangeModel};
+use crate::grid::{BeamCone, GridMap};
+use crate::robo::{normalize_angle, odometry_deltas, DiffDrive, OdometryNoise, RobotAccess, RobotStartBelief};
+
+// Step sizes for the numeric jacobians.
+const EPS_POSITION: f64 = 0.05;
+const EPS_HEADING: f64 = 0.01;
+
+// Extended Kalman filter pose tracker (Probabilistic Robotics, table 7.2).
+// The state is a single gaussian over (x, y, theta), so this only works if the start pose is
+// roughly known. The motion model is the differential drive driven by the wheel velocities and
+// the measurement model is the ray cast of the ultrasonic beam against the map, both linearized
+// with numeric jacobians because the ray cast has no closed form.
+#[derive(Debug)]
+pub struct Ekf {
+    mean: Vector3<f64>,
+    covariance: Matrix3<f64>,
+    map: GridMap,
+    max_range: f64,
+    beam: BeamCone,
+    drive: DiffDrive,
+    range_model: RangeModel,
+    motion_noise: OdometryNoise,
+    // Readings whose squared mahalanobis distance is larger than this are thrown away as outliers.
+    pub gate: f64,
+}
+
+impl Ekf {
+    pub fn new(map: GridMap, max_range: f64, mean: Vector3<f64>, covariance: Matrix3<f64>) -> Self {
+        Ekf {
+            mean,
+            covariance,
+            map,
+            max_range,
+            beam: BeamCone::default(),
+            drive: DiffDrive::default(),
+            range_model: RangeModel::default(),
+            motion_noise: OdometryNoise::default(),
+            // 99 % of a chi-squared distribution with one degree of freedom.
+            gate: 6.63,
+        }
+    }
+
+    // Starts at mean on the map of the robot.
+    pub fn from_robot<R: RobotAccess>(robot: &R, mean: Vector3<f64>, covariance: Matrix3<f64>) -> Self {
+        Ekf::new(GridMap::from_points(&robot.get_map()), robot.get_hcsr04_max_range(), mean, covariance)
+    }
+
+    pub fn set_beam_cone(&mut self, beam: BeamCone) {
+        self.beam = beam;
+    }
+
+    pub fn set_drive(&mut self, drive: DiffDrive) {
+        self.drive = drive;
+    }
+
+    pub fn set_range_model(&mut self, range_model: RangeModel) {
+        self.range_model = range_model;
+    }
+
+    pub fn set_motion_noise(&mut self, motion_noise: OdometryNoise) {
+        self.motion_noise = motion_noise;
+    }
+
+    // Corrects the pose with one reading. A missing echo or a pose from where the map expects
+    // no echo can't be linearized, so these readings are skipped, as are outliers.
+    // Returns true if the reading was used.
+    pub fn correct(&mut self, measured: Option<f64>) -> bool {
+        let z = match measured {
+            Some(z) => z,
+            None => return false,
+        };
+        let expected = match self.expected(self.mean) {
+            Some(e) => e,
+            None => return false,
+        };
+        let h = match self.measurement_jacobian() {
+            Some(h) => h,
+            None => return false,
+        };
+        let sigma = (self.range_model.sigma_hit + self.range_model.sigma_hit_per_m * expected).max(self.range_model.min_sigma);
+        let r = sigma.powi(2);
+        let s = (h * self.covariance * h.transpose())[(0, 0)] + r;
+        let y = z - expected;
+        if y.powi(2) / s > self.gate {
+            return false;
+        }
+        let k = self.covariance * h.transpose() / s;
+        self.mean += k * y;
+        self.mean.z = normalize_angle(self.mean.z);
+        // Joseph form, stays symmetric and positive definite.
+        let i_kh = Matrix3::identity() - k * h;
+        self.covariance = i_kh * self.covariance * i_kh.transpose() + k * r * k.transpose();
+        true
+    }
+
+    fn expected(&self, pose: Vector3<f64>) -> Option<f64> {
+        self.map.cone_hit(pose, &self.beam, self.max_range).map(|h| h.dist)
+    }
+
+    // Gradient of the expected range with respect to the pose. Uses central differences and
+    // falls back to one side if the ray misses on the other.
+    fn measurement_jacobian(&self) -> Option<RowVector3<f64>> {
+        let center = self.expected(self.mean)?;
+        let mut h = RowVector3::zeros();
+        for (k, eps) in [(0, EPS_POSITION), (1, EPS_POSITION), (2, EPS_HEADING)] {
+            let mut d = Vector3::zeros();
+            d[k] = eps;
+            h[k] = match (self.expected(self.mean + d), self.expected(self.mean - d)) {
+                (Some(a), Some(b)) => (a - b) / (2. * eps),
+                (Some(a), None) => (a - center) / eps,
+                (None, Some(b)) => (center - b) / eps,
+                (None, None) => return None,
+            };
+        }
+        Some(h)
+    }
+}
+
+impl Localizer for Ekf {
+    fn name(&self) -> &'static str {
+        "EKF"
+    }
+
+    // Sets mean and covariance from the start belief. A gaussian can't hold several
+    // hypotheses, so those are merged into their mean and covariance.
+    fn reset(&mut self, start: &RobotStartBelief) {
+        let (mean, covariance) = match start {
+            RobotStartBelief::PointMass(p) => (*p, Matrix3::from_diagonal(&Vector3::new(0.01, 0.01, 0.001))),
+            RobotStartBelief::Gaussian { mean, covariance } => (*mean, *covariance),
+            RobotStartBelief::Region { min, max, heading_range } => {
+                let width = match heading_range.1 - heading_range.0 >= std::f64::consts::TAU {
+                    true => std::f64::consts::TAU,
+                    false => (heading_range.1 - heading_range.0).rem_euclid(std::f64::consts::TAU),
+                };
+                let c = (min + max) / 2.;
+                let size = max - min;
+                // Variance of a uniform distribution is width^2 / 12.
+                (
+                    Vector3::new(c.x, c.y, normalize_angle(heading_range.0 + width / 2.)),
+                    Matrix3::from_diagonal(&Vector3::new(size.x.powi(2) / 12., size.y.powi(2) / 12., width.powi(2) / 12.)),
+                )
+            },
+            RobotStartBelief::Hypotheses(h) => weighted_moments(h),
+            RobotStartBelief::Uniform => {
+                let min = self.map.origin();
+                let max = min + Vector2::new(self.map.width() as f64, self.map.height() as f64);
+                let c = (min + max) / 2.;
+                let size = max - min;
+                (
+                    Vector3::new(c.x, c.y, 0.),
+                    Matrix3::from_diagonal(&Vector3::new(size.x.powi(2) / 12., size.y.powi(2) / 12., std::f64::consts::PI.powi(2) / 3.)),
+                )
+            },
+        };
+        self.mean = mean;
+        self.covariance = covariance;
+    }
+
+    // Moves the mean with the wheel velocities and grows the covariance by the linearized
+    // motion and the odometry noise (Probabilistic Robotics, table 7.3).
+    fn predict(&mut self, left: f64, right: f64, dt: f64) {
+        let f = |pose: Vector3<f64>, l: f64, r: f64| self.drive.integrate(pose, l, r, dt);
+        let next = f(self.mean, left, right);
+        // Jacobian of the motion with respect to the pose.
+        let mut g = Matrix3::zeros();
+        for (k, eps) in [(0, EPS_POSITION), (1, EPS_POSITION), (2, EPS_HEADING)] {
+            let mut d = Vector3::zeros();
+            d[k] = eps;
+            g.set_column(k, &pose_diff(f(self.mean + d, left, right), f(self.mean - d, left, right), 2. * eps));
+        }
+        // Noise of the motion as rotation, translation and rotation and the jacobian of the
+        // pose with respect to these three.
+        let (rot1, trans, rot2) = odometry_deltas(self.mean, next);
+        let a = &self.motion_noise;
+        let m = Matrix3::from_diagonal(&Vector3::new(
+            a.alpha1 * rot1.powi(2) + a.alpha2 * trans.powi(2),
+            a.alpha3 * trans.powi(2) + a.alpha4 * (rot1.powi(2) + rot2.powi(2)),
+            a.alpha1 * rot2.powi(2) + a.alpha2 * trans.powi(2),
+        ));
+        let heading = self.mean.z + rot1;
+        let v = Matrix3::new(
+            -trans * heading.sin(), heading.cos(), 0.,
+            trans * heading.cos(), heading.sin(), 0.,
+            1., 0., 1.,
+        );
+        self.mean = next;
+        self.covariance = g * self.covariance * g.transpose() + v * m * v.transpose();
+    }
+
+    fn update(&mut self, measured: Option<f64>) {
+        self.correct(measured);
+    }
+
+    fn estimate(&self) -> (Vector3<f64>, Matrix3<f64>) {
+        (self.mean, self.covariance)
+    }
+
+    // Only the mean, the covariance is shown as std dev.
+    fn samples(&self) -> Vec<(Vector2<f64>, f64)> {
+        vec![(self.mean.xy(), 1.)]
+    }
+}
+
+// (a - b) / step with the heading difference wrapped.
+fn pose_diff(a: Vector3<f64>, b: Vector3<f64>, step: f64) -> Vector3<f64> {
+    let mut d = a - b;
+    d.z = normalize_angle(d.z);
+    d / step
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::app::small_map;
+
+    // At the start of the small map, looking back at the outer wall.
+    fn ekf(covariance: Matrix3<f64>) -> Ekf {
+        Ekf::new(GridMap::from_points(&small_map()), 5., Vector3::new(2.5, 2.5, PI), covariance)
+    }
+
+    #[test]
+    fn gate_throws_away_a_max_range_reading() {
+        let mut ekf = ekf(Matrix3::from_diagonal(&Vector3::new(0.01, 0.01, 0.001)));
+        let start = ekf.mean;
+        let expected = ekf.expected(start).unwrap();
+        assert!(expected < 4., "{}", expected);
+        // Nothing close to the pose we are sure about gives a reading at the max range.
+        assert!(!ekf.correct(Some(5.)));
+        assert_eq!(ekf.mean, start);
+        // A reading close to the expected one is taken and moves the mean.
+        assert!(ekf.correct(Some(expected + 0.05)));
+        assert_ne!(ekf.mean, start);
+    }
+
+    #[test]
+    fn readings_shrink_the_covariance() {
+        let start_covariance = Matrix3::from_diagonal(&Vector3::new(0.25, 0.25, 0.05));
+        let mut ekf = ekf(start_covariance);
+        let start = ekf.mean;
+        let z = ekf.expected(start);
+        for _ in 0..5 {
+            let var_x = ekf.covariance[(0, 0)];
+            assert!(ekf.correct(z));
+            // The beam runs along x, every reading pins x down a bit more.
+            assert!(ekf.covariance[(0, 0)] < var_x, "{}", ekf.covariance);
+        }
+        assert!(ekf.covariance.determinant() < start_covariance.determinant());
+        assert!(ekf.covariance.symmetric_eigenvalues().iter().all(|e| *e > 0.));
+        // The readings are exactly the expected ones, so the mean stays.
+        assert!(pose_diff(ekf.mean, start, 1.).norm() < 1e-9, "{}", ekf.mean);
+    }
+}
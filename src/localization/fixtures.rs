@@ -0,0 +1,22 @@
+// Setup the localizer tests share: the simulated robot on the small map.
+
+use nalgebra::Vector3;
+
+use super::Localizer;
+use crate::robo::sim_pi::{MyPiSim, NoiseConfig};
+use crate::robo::RobotAccess;
+
+// The simulated robot standing at start, with a 5 m sensor.
+pub fn small_sim(noise: NoiseConfig, start: Vector3<f64>) -> MyPiSim {
+    let mut sim = MyPiSim::new(5., crate::app::small_map(), noise);
+    sim.set_robot_position(start);
+    sim
+}
+
+// Drives the robot for dt seconds and hands the wheel velocities and the reading to the localizer.
+pub fn step<L: Localizer>(sim: &mut MyPiSim, localizer: &mut L, dt: f64) {
+    let (vl, vr) = sim.get_wheel_velo();
+    sim.step(dt);
+    localizer.predict(vl, vr, dt);
+    localizer.update(sim.get_hcsr04_dist().unwrap());
+}
@@ -0,0 +1,413 @@
+use std::f64::consts::TAU;
+
+use nalgebra::{Matrix3, Vector2, Vector3};
+
+use super::{Localizer, RangeModel};
+use crate::grid::{BeamCone, GridMap, PositionType};
+use crate::robo::{normalize_angle, odometry_deltas, DiffDrive, OdometryNoise, RobotAccess, RobotStartBelief};
+
+// Consts for the histogram filter.
+// Part of the belief that spreads to each of the 4 neighbour cells and the 2 neighbour
+// heading bins when the belief is moved. The odometry noise can ask for more, up to the max.
+const BELIEF_BLUR: f64 = 0.03;
+const BELIEF_HEADING_BLUR: f64 = 0.05;
+const BELIEF_MAX_BLUR: f64 = 0.15;
+const BELIEF_MAX_HEADING_BLUR: f64 = 0.1;
+// Odometry translation (in cells) that triggers a predict step.
+const BELIEF_MIN_TRANS: f64 = 0.5;
+// Default number of heading bins, 10 degree each.
+const BELIEF_HEADING_BINS: usize = 36;
+// Smallest std deviation of the range likelihood. Below that the grid is too coarse.
+const BELIEF_MIN_SIGMA: f64 = 0.5;
+
+// Discrete Bayes filter (Probabilistic Robotics, ch. 4.1) over the map cells and heading bins.
+#[derive(Debug)]
+pub struct HistogramFilter {
+    // Indexed as belief[x][y][heading], same cells as the map.
+    belief: Vec<Vec<Vec<f64>>>,
+    heading_bins: usize,
+    map: GridMap,
+    max_range: f64,
+    beam: BeamCone,
+    drive: DiffDrive,
+    range_model: RangeModel,
+    motion_noise: OdometryNoise,
+    // Pose integrated from the wheel velocities and that pose at the last predict step.
+    // Only the motion between the two matters, so this starts at zero.
+    odometry: Vector3<f64>,
+    belief_odometry: Vector3<f64>,
+    // Noise free reading for every cell and heading bin. Empty if it has to be computed again.
+    expected_ranges: Vec<Vec<Vec<Option<f64>>>>,
+}
+
+impl HistogramFilter {
+    pub fn new(map: GridMap, max_range: f64) -> Self {
+        let mut t = HistogramFilter {
+            belief: Vec::new(),
+            heading_bins: BELIEF_HEADING_BINS,
+            map,
+            max_range,
+            beam: BeamCone::default(),
+            drive: DiffDrive::default(),
+            range_model: RangeModel { min_sigma: BELIEF_MIN_SIGMA, ..RangeModel::default() },
+            motion_noise: OdometryNoise::default(),
+            odometry: Vector3::zeros(),
+            belief_odometry: Vector3::zeros(),
+            expected_ranges: Vec::new(),
+        };
+        t.belief = t.uniform_belief();
+        t
+    }
+
+    // Grid over the map of the robot, uniform until the first reset.
+    pub fn from_robot<R: RobotAccess>(robot: &R) -> Self {
+        HistogramFilter::new(GridMap::from_points(&robot.get_map()), robot.get_hcsr04_max_range())
+    }
+
+    pub fn set_beam_cone(&mut self, beam: BeamCone) {
+        self.beam = beam;
+        self.expected_ranges = Vec::new();
+    }
+
+    pub fn set_drive(&mut self, drive: DiffDrive) {
+        self.drive = drive;
+    }
+
+    // The grid can't resolve less than BELIEF_MIN_SIGMA, so min_sigma stays at least that.
+    pub fn set_range_model(&mut self, range_model: RangeModel) {
+        self.range_model = RangeModel { min_sigma: range_model.min_sigma.max(BELIEF_MIN_SIGMA), ..range_model };
+    }
+
+    pub fn set_motion_noise(&mut self, motion_noise: OdometryNoise) {
+        self.motion_noise = motion_noise;
+    }
+
+    // Sets the number of heading bins. The belief keeps its distribution over the
+    // cells but forgets the heading.
+    pub fn set_heading_bins(&mut self, heading_bins: usize) {
+        self.heading_bins = heading_bins.max(1);
+        self.expected_ranges = Vec::new();
+        let n = self.heading_bins;
+        self.belief = self.belief
+            .iter()
+            .map(|col| col.iter().map(|bins| vec![bins.iter().sum::<f64>() / n as f64; n]).collect())
+            .collect();
+    }
+
+    // The belief summed up over all headings, given as the lower left corner of the cell.
+    // Only cells with a probability larger than zero are returned.
+    pub fn cell_belief(&self) -> Vec<(Vector2<f64>, f64)> {
+        let mut v = Vec::new();
+        for (i, col) in self.belief.iter().enumerate() {
+            for (j, bins) in col.iter().enumerate() {
+                let p: f64 = bins.iter().sum();
+                if p > 0. {
+                    v.push((self.map.origin() + Vector2::new(i as f64, j as f64), p));
+                }
+            }
+        }
+        v
+    }
+
+    // Center of heading bin k. Bin 0 is centered on heading 0.
+    fn bin_heading(&self, k: usize) -> f64 {
+        normalize_angle(k as f64 * TAU / self.heading_bins as f64)
+    }
+
+    // Index of the heading bin that contains the angle a.
+    fn heading_bin(&self, a: f64) -> usize {
+        let width = TAU / self.heading_bins as f64;
+        (a.rem_euclid(TAU) / width).round() as usize % self.heading_bins
+    }
+
+    // Belief with the given weights in the cells and heading bins of the poses.
+    fn hypotheses_belief(&self, hypotheses: &[(Vector3<f64>, f64)]) -> Vec<Vec<Vec<f64>>> {
+        let mut b = vec![vec![vec![0.; self.heading_bins]; self.map.height()]; self.map.width()];
+        for (p, w) in hypotheses {
+            if let Some((i, j)) = self.map.cell_index(p.x, p.y) {
+                b[i][j][self.heading_bin(p.z)] += w.max(0.);
+            }
+        }
+        b
+    }
+
+    // Belief from the density of the start belief at the center of every cell and heading bin.
+    fn density_belief(&self, start: &RobotStartBelief) -> Vec<Vec<Vec<f64>>> {
+        (0..self.map.width())
+            .map(|i| (0..self.map.height())
+                .map(|j| {
+                    let center = self.map.cell_center(i, j);
+                    (0..self.heading_bins)
+                        .map(|k| start.density(Vector3::new(center.x, center.y, self.bin_heading(k))))
+                        .collect()
+                })
+                .collect())
+            .collect()
+    }
+
+    // Equal probability for every free cell and heading.
+    fn uniform_belief(&self) -> Vec<Vec<Vec<f64>>> {
+        let mut b: Vec<Vec<Vec<f64>>> = self.map
+            .cells()
+            .iter()
+            .map(|col| col.iter().map(|c| vec![if *c == PositionType::Empty { 1. } else { 0. }; self.heading_bins]).collect())
+            .collect();
+        normalize_belief(&mut b);
+        b
+    }
+
+    // Moves the belief by the odometry motion since the last time. We wait until the odometry
+    // moved half a cell or half a heading bin, otherwise small motions would get lost in the grid.
+    // Then every cell and heading is moved by the odometry motion turned into its own heading.
+    // The mass is split between the neighbouring cells and bins and blurred a bit.
+    fn shift_belief(&mut self) {
+        if self.belief.is_empty() {
+            return;
+        }
+        let n = self.heading_bins;
+        let bin_width = TAU / n as f64;
+        let (rot1, trans, rot2) = odometry_deltas(self.belief_odometry, self.odometry);
+        if trans.abs() < BELIEF_MIN_TRANS && normalize_angle(rot1 + rot2).abs() < bin_width / 2. {
+            return;
+        }
+        self.belief_odometry = self.odometry;
+        let (w, h) = (self.belief.len() as i64, self.belief[0].len() as i64);
+        let mut moved = vec![vec![vec![0.; n]; h as usize]; w as usize];
+        for i in 0..w {
+            for j in 0..h {
+                for k in 0..n {
+                    let p = self.belief[i as usize][j as usize][k];
+                    if p == 0. {
+                        continue;
+                    }
+                    let theta = self.bin_heading(k);
+                    // New position in cell index coordinates, shifted so cell centers are integers.
+                    let x = i as f64 + trans * (theta + rot1).cos();
+                    let y = j as f64 + trans * (theta + rot1).sin();
+                    let fk = (theta + rot1 + rot2).rem_euclid(TAU) / bin_width;
+                    let (x0, y0, k0) = (x.floor(), y.floor(), fk.floor());
+                    let (wx, wy, wk) = (x - x0, y - y0, fk - k0);
+                    for (di, pi) in [(0, 1. - wx), (1, wx)] {
+                        for (dj, pj) in [(0, 1. - wy), (1, wy)] {
+                            let (ni, nj) = (x0 as i64 + di, y0 as i64 + dj);
+                            if ni < 0 || nj < 0 || ni >= w || nj >= h {
+                                continue;
+                            }
+                            for (dk, pk) in [(0, 1. - wk), (1, wk)] {
+                                let nk = (k0 as usize + dk) % n;
+                                moved[ni as usize][nj as usize][nk] += p * pi * pj * pk;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        // Blur, the robot could have slipped into a neighbour cell or turned a bit more.
+        // A part b to each neighbour adds a variance of 2 b cells^2 along each axis, that's
+        // where the odometry noise goes. The translation noise is split over both axes.
+        let a = &self.motion_noise;
+        let trans_var = a.alpha3 * trans.powi(2) + a.alpha4 * (rot1.powi(2) + rot2.powi(2));
+        let rot_var = a.alpha1 * (rot1.powi(2) + rot2.powi(2)) + 2. * a.alpha2 * trans.powi(2);
+        let blur = (trans_var / 4.).clamp(BELIEF_BLUR, BELIEF_MAX_BLUR);
+        let heading_blur = (rot_var / (2. * bin_width.powi(2))).clamp(BELIEF_HEADING_BLUR, BELIEF_MAX_HEADING_BLUR);
+        let mut blurred = vec![vec![vec![0.; n]; h as usize]; w as usize];
+        for i in 0..w {
+            for j in 0..h {
+                for k in 0..n {
+                    let p = moved[i as usize][j as usize][k];
+                    if p == 0. {
+                        continue;
+                    }
+                    blurred[i as usize][j as usize][k] += p * (1. - 4. * blur - 2. * heading_blur);
+                    for (ni, nj) in [(i + 1, j), (i - 1, j), (i, j + 1), (i, j - 1)] {
+                        if ni >= 0 && nj >= 0 && ni < w && nj < h {
+                            blurred[ni as usize][nj as usize][k] += p * blur;
+                        }
+                    }
+                    blurred[i as usize][j as usize][(k + 1) % n] += p * heading_blur;
+                    blurred[i as usize][j as usize][(k + n - 1) % n] += p * heading_blur;
+                }
+            }
+        }
+        self.belief = blurred;
+        self.zero_walls_and_normalize();
+    }
+
+    // Noise free reading for the center of every cell and every heading bin.
+    fn compute_expected_ranges(&mut self) {
+        self.expected_ranges = (0..self.map.width())
+            .map(|i| (0..self.map.height())
+                .map(|j| {
+                    if self.map.get(i, j) == PositionType::Wall {
+                        return Vec::new();
+                    }
+                    let center = self.map.cell_center(i, j);
+                    (0..self.heading_bins)
+                        .map(|k| {
+                            let pose = Vector3::new(center.x, center.y, self.bin_heading(k));
+                            self.map.cone_hit(pose, &self.beam, self.max_range).map(|h| h.dist)
+                        })
+                        .collect()
+                })
+                .collect())
+            .collect();
+    }
+
+    // Walls can't hold the robot. If nothing is left we are lost and start over with a uniform belief.
+    fn zero_walls_and_normalize(&mut self) {
+        for (col, map_col) in self.belief.iter_mut().zip(self.map.cells()) {
+            for (bins, cell) in col.iter_mut().zip(map_col) {
+                if *cell == PositionType::Wall {
+                    bins.iter_mut().for_each(|p| *p = 0.);
+                }
+            }
+        }
+        if normalize_belief(&mut self.belief) == 0. {
+            self.belief = self.uniform_belief();
+        }
+    }
+}
+
+impl Localizer for HistogramFilter {
+    fn name(&self) -> &'static str {
+        "Histogram"
+    }
+
+    // Turns the start belief into the grid. If no free cell gets any probability
+    // (e.g. a point mass in a wall) we know nothing and start with a uniform belief.
+    fn reset(&mut self, start: &RobotStartBelief) {
+        self.belief_odometry = self.odometry;
+        self.belief = match start {
+            RobotStartBelief::PointMass(p) => self.hypotheses_belief(&[(*p, 1.)]),
+            RobotStartBelief::Hypotheses(h) => self.hypotheses_belief(h),
+            RobotStartBelief::Uniform => self.uniform_belief(),
+            RobotStartBelief::Gaussian { .. } | RobotStartBelief::Region { .. } => self.density_belief(start),
+        };
+        self.zero_walls_and_normalize();
+    }
+
+    // Integrates the wheel velocities and moves the belief once the motion is large enough.
+    fn predict(&mut self, left: f64, right: f64, dt: f64) {
+        self.odometry = self.drive.integrate(self.odometry, left, right, dt);
+        self.shift_belief();
+    }
+
+    // Every free cell and heading is weighted by how well the reading fits the expected
+    // reading from there.
+    fn update(&mut self, measured: Option<f64>) {
+        if self.belief.is_empty() {
+            return;
+        }
+        if self.expected_ranges.is_empty() {
+            self.compute_expected_ranges();
+        }
+        for i in 0..self.belief.len() {
+            for j in 0..self.belief[i].len() {
+                for k in 0..self.heading_bins {
+                    if self.belief[i][j][k] == 0. {
+                        continue;
+                    }
+                    self.belief[i][j][k] *= self.range_model.likelihood(self.expected_ranges[i][j][k], measured, self.max_range);
+                }
+            }
+        }
+        self.zero_walls_and_normalize();
+    }
+
+    // Mean and covariance over the cell centers and bin headings.
+    fn estimate(&self) -> (Vector3<f64>, Matrix3<f64>) {
+        let samples = self.belief.iter().enumerate().flat_map(|(i, col)| {
+            col.iter().enumerate().flat_map(move |(j, bins)| {
+                bins.iter().enumerate().map(move |(k, p)| (i, j, k, *p))
+            })
+        });
+        let poses: Vec<(Vector3<f64>, f64)> = samples
+            .filter(|(_, _, _, p)| *p > 0.)
+            .map(|(i, j, k, p)| {
+                let c = self.map.cell_center(i, j);
+                (Vector3::new(c.x, c.y, self.bin_heading(k)), p)
+            })
+            .collect();
+        super::weighted_moments(&poses)
+    }
+
+    fn samples(&self) -> Vec<(Vector2<f64>, f64)> {
+        self.cell_belief()
+            .into_iter()
+            .map(|(v, p)| (v + Vector2::new(0.5, 0.5), p))
+            .collect()
+    }
+}
+
+// Scales the belief so it sums up to one and returns the sum before.
+fn normalize_belief(belief: &mut [Vec<Vec<f64>>]) -> f64 {
+    let sum: f64 = belief.iter().flatten().flatten().sum();
+    if sum > 0. {
+        belief.iter_mut().flatten().flatten().for_each(|b| *b /= sum);
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::small_map;
+    use crate::localization::fixtures::{small_sim, step};
+    use crate::robo::sim_pi::NoiseConfig;
+    use crate::robo::OdometryNoise;
+
+    #[test]
+    fn belief_follows_the_robot() {
+        let start = Vector3::new(2.5, 2.5, 0.);
+        let mut sim = small_sim(NoiseConfig::none(), start);
+        sim.set_odometry_noise(OdometryNoise::none());
+        let mut h = HistogramFilter::from_robot(&sim);
+        h.reset(&RobotStartBelief::PointMass(start));
+        sim.set_wheel_velo(10., 10.);
+        for _ in 0..20 {
+            step(&mut sim, &mut h, 0.1);
+        }
+        // The robot drove 2 cells along x, the mean of the belief has to follow it.
+        let sum: f64 = h.cell_belief().iter().map(|(_, p)| p).sum();
+        assert!((sum - 1.).abs() < 1e-9);
+        let (mean, _) = h.estimate();
+        let p = sim.robot_position();
+        assert!((mean.xy() - p.xy()).norm() < 0.5, "{mean} vs {p}");
+        // And the heading stays where it started.
+        assert!(mean.z.abs() < 0.1, "{mean}");
+    }
+
+    #[test]
+    fn start_beliefs_fill_the_grid() {
+        let mut h = HistogramFilter::new(GridMap::from_points(&small_map()), 5.);
+        h.set_heading_bins(4);
+        h.reset(&RobotStartBelief::Region {
+            min: Vector2::new(2., 2.),
+            max: Vector2::new(4., 4.),
+            heading_range: (-0.1, 0.1),
+        });
+        let cells = h.cell_belief();
+        assert_eq!(cells.len(), 4);
+        assert!(cells.iter().all(|(v, p)| v.x >= 2. && v.x < 4. && (p - 0.25).abs() < 1e-9));
+        // All of it in the bin around heading 0.
+        assert_eq!(h.belief.iter().flatten().map(|bins| bins[0]).sum::<f64>(), 1.);
+
+        h.reset(&RobotStartBelief::Hypotheses(vec![
+            (Vector3::new(2.5, 2.5, 0.), 3.),
+            (Vector3::new(10.5, 2.5, 0.), 1.),
+            // Inside the outer wall, this one is dropped.
+            (Vector3::new(0.5, 0.5, 0.), 5.),
+        ]));
+        let mut cells = h.cell_belief();
+        cells.sort_by(|a, b| b.1.total_cmp(&a.1));
+        assert_eq!(cells, vec![(Vector2::new(2., 2.), 0.75), (Vector2::new(10., 2.), 0.25)]);
+
+        h.reset(&RobotStartBelief::Uniform);
+        let cells = h.cell_belief();
+        assert!(cells.iter().all(|(v, _)| h.map.is_free(v.x, v.y)));
+        assert_eq!(cells.len(), h.map.free_cells().len());
+        // Every heading bin of every free cell.
+        let nonzero = h.belief.iter().flatten().flatten().filter(|p| **p > 0.).count();
+        assert_eq!(nonzero, h.map.free_cells().len() * h.heading_bins);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use nalgebra::{Matrix3, Vector3};
+
+use crate::robo::normalize_angle;
+
+// Position error (in cells) below which the estimate counts as converged.
+const CONVERGED_DIST: f64 = 0.5;
+// The error has to stay below CONVERGED_DIST that long (s) before we call it converged.
+const CONVERGED_HOLD: f64 = 2.;
+// 95 % of a chi-squared distribution with three degrees of freedom. A consistent filter
+// has its NEES below this in 95 % of the steps.
+const NEES_BOUND: f64 = 7.81;
+// Number of samples kept for the error plot.
+const HISTORY_LEN: usize = 200;
+
+// One comparison of the estimate with the ground truth.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorSample {
+    // Seconds since the metrics were reset.
+    pub time: f64,
+    pub position_error: f64,
+    // Absolute heading error in rad.
+    pub heading_error: f64,
+    // Normalized estimation error squared, e^T P^-1 e. None if the covariance can't be inverted.
+    pub nees: Option<f64>,
+}
+
+// Accuracy of a localizer against the ground truth. Only the simulation knows the ground truth,
+// so this is only filled there.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    time: f64,
+    steps: usize,
+    sum_sq_position: f64,
+    sum_sq_heading: f64,
+    nees_sum: f64,
+    nees_steps: usize,
+    nees_inside: usize,
+    // Start of the current run below CONVERGED_DIST.
+    below_since: Option<f64>,
+    converged_at: Option<f64>,
+    history: VecDeque<ErrorSample>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    // Forgets everything, e.g. after the localizer was switched.
+    pub fn reset(&mut self) {
+        *self = Metrics::default();
+    }
+
+    // Compares the estimate (mean and covariance) with the true pose dt seconds after the last call.
+    pub fn record(&mut self, dt: f64, truth: Vector3<f64>, mean: Vector3<f64>, covariance: Matrix3<f64>) {
+        self.time += dt;
+        let mut e = mean - truth;
+        e.z = normalize_angle(e.z);
+        let position_error = e.xy().norm();
+        let heading_error = e.z.abs();
+        let nees = covariance.try_inverse().map(|inv| (e.transpose() * inv * e)[(0, 0)]);
+
+        self.steps += 1;
+        self.sum_sq_position += position_error.powi(2);
+        self.sum_sq_heading += heading_error.powi(2);
+        if let Some(n) = nees {
+            self.nees_sum += n;
+            self.nees_steps += 1;
+            if n <= NEES_BOUND {
+                self.nees_inside += 1;
+            }
+        }
+        match position_error < CONVERGED_DIST {
+            true => {
+                let since = *self.below_since.get_or_insert(self.time);
+                if self.converged_at.is_none() && self.time - since >= CONVERGED_HOLD {
+                    self.converged_at = Some(since);
+                }
+            },
+            false => self.below_since = None,
+        }
+
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(ErrorSample { time: self.time, position_error, heading_error, nees });
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub fn last(&self) -> Option<ErrorSample> {
+        self.history.back().copied()
+    }
+
+    // Root mean squared position error over all steps.
+    pub fn position_rmse(&self) -> Option<f64> {
+        (self.steps > 0).then(|| (self.sum_sq_position / self.steps as f64).sqrt())
+    }
+
+    // Root mean squared heading error over all steps.
+    pub fn heading_rmse(&self) -> Option<f64> {
+        (self.steps > 0).then(|| (self.sum_sq_heading / self.steps as f64).sqrt())
+    }
+
+    // Average NEES. Should be about 3 (the state dimension) if the covariance fits the error.
+    // Larger means the filter is overconfident, smaller means it is too careful.
+    pub fn mean_nees(&self) -> Option<f64> {
+        (self.nees_steps > 0).then(|| self.nees_sum / self.nees_steps as f64)
+    }
+
+    // Part of the steps with the NEES inside the 95 % bound.
+    pub fn nees_consistency(&self) -> Option<f64> {
+        (self.nees_steps > 0).then(|| self.nees_inside as f64 / self.nees_steps as f64)
+    }
+
+    // Time from the reset until the position error went below CONVERGED_DIST and stayed there
+    // for CONVERGED_HOLD seconds.
+    pub fn time_to_convergence(&self) -> Option<f64> {
+        self.converged_at
+    }
+
+    // Recent errors for plotting, oldest first.
+    pub fn history(&self) -> &VecDeque<ErrorSample> {
+        &self.history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_and_convergence() {
+        let mut m = Metrics::new();
+        let truth = Vector3::new(5., 5., 3.);
+        // 1 s far away, then on the spot.
+        for _ in 0..10 {
+            m.record(0.1, truth, Vector3::new(8., 9., 3.), Matrix3::identity());
+        }
+        assert_eq!(m.time_to_convergence(), None);
+        // The heading error wraps around.
+        for _ in 0..30 {
+            m.record(0.1, truth, Vector3::new(5., 5., 3. - std::f64::consts::TAU), Matrix3::identity());
+        }
+        assert!((m.time_to_convergence().unwrap() - 1.1).abs() < 1e-9);
+        assert!((m.position_rmse().unwrap() - (10. * 25. / 40_f64).sqrt()).abs() < 1e-9);
+        assert!(m.heading_rmse().unwrap() < 1e-9);
+        assert!((m.last().unwrap().nees.unwrap()).abs() < 1e-9);
+        assert_eq!(m.nees_consistency(), Some(0.75));
+
+        m.reset();
+        assert_eq!(m.position_rmse(), None);
+        assert!(m.history().is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use std::collections::HashSet;
+use std::f64::consts::{PI, TAU};
+
+use nalgebra::{Matrix3, Vector2, Vector3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+
+use super::{weighted_moments, Localizer, RangeModel};
+use crate::grid::{BeamCone, GridMap};
+use crate::robo::{normalize_angle, DiffDrive, OdometryNoise, RobotAccess, RobotStartBelief};
+
+// Tries to find a free pose in a region before we give up and take what we have.
+const REGION_SAMPLE_TRIES: usize = 100;
+
+// Settings for the adaptive particle filter.
+// KLD sampling (Probabilistic Robotics, table 8.4) draws particles until the histogram of the
+// new set over bins of bin_size has enough samples that the KL divergence to the true
+// distribution is below kld_epsilon with probability 1 - delta. kld_z is the upper 1 - delta
+// quantile of the standard normal distribution.
+// Augmented MCL (table 8.3) adds random particles when the short term average likelihood
+// (alpha_fast) drops below the long term average (alpha_slow), e.g. after the robot got kidnapped.
+// A single bad reading also pulls the short term average down a bit, so we wait until it fell
+// below drop_ratio of the long term average, that takes a few bad readings in a row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveConfig {
+    pub min_particles: usize,
+    pub max_particles: usize,
+    pub kld_epsilon: f64,
+    pub kld_z: f64,
+    // Bin size in x, y and theta.
+    pub bin_size: Vector3<f64>,
+    pub alpha_slow: f64,
+    pub alpha_fast: f64,
+    pub drop_ratio: f64,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        AdaptiveConfig {
+            min_particles: 100,
+            max_particles: 5000,
+            kld_epsilon: 0.05,
+            // delta = 0.01
+            kld_z: 2.326,
+            bin_size: Vector3::new(0.5, 0.5, 10_f64.to_radians()),
+            alpha_slow: 0.001,
+            alpha_fast: 0.1,
+            drop_ratio: 0.5,
+        }
+    }
+}
+
+impl AdaptiveConfig {
+    // Number of particles needed for k non empty bins (Wilson-Hilferty approximation).
+    pub fn kld_bound(&self, k: usize) -> usize {
+        if k <= 1 {
+            return self.min_particles;
+        }
+        let k = (k - 1) as f64;
+        let a = 2. / (9. * k);
+        let m = k / (2. * self.kld_epsilon) * (1. - a + a.sqrt() * self.kld_z).powi(3);
+        (m.ceil() as usize).clamp(self.min_particles, self.max_particles)
+    }
+
+    fn bin(&self, pose: Vector3<f64>) -> (i64, i64, i64) {
+        (
+            (pose.x / self.bin_size.x).floor() as i64,
+            (pose.y / self.bin_size.y).floor() as i64,
+            ((normalize_angle(pose.z) + PI) / self.bin_size.z).floor() as i64,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub pose: Vector3<f64>,
+    pub weight: f64,
+}
+
+// Monte Carlo localization (Probabilistic Robotics, table 8.2). Every particle is a guess of
+// the pose. They are moved with the wheel velocities, weighted by how well the ultrasonic
+// reading fits the map from their pose and resampled when too few of them carry the weight.
+#[derive(Debug)]
+pub struct ParticleFilter {
+    particles: Vec<Particle>,
+    map: GridMap,
+    // The free cells of the map, random particles are drawn from them.
+    free: Vec<(usize, usize)>,
+    max_range: f64,
+    beam: BeamCone,
+    drive: DiffDrive,
+    motion_noise: OdometryNoise,
+    range_model: RangeModel,
+    rng: StdRng,
+    // None for a fixed number of particles.
+    adaptive: Option<AdaptiveConfig>,
+    // Long and short term average of the measurement likelihood, for augmented MCL.
+    w_slow: f64,
+    w_fast: f64,
+    // Readings since the last reset. Until there are enough for alpha, the averages are
+    // plain means, so the first reading doesn't stick in w_slow for ages.
+    readings: usize,
+}
+
+impl ParticleFilter {
+    pub fn new(map: GridMap, max_range: f64, count: usize, seed: u64) -> Self {
+        let mut t = ParticleFilter {
+            particles: Vec::new(),
+            free: map.free_cells(),
+            map,
+            max_range,
+            beam: BeamCone::default(),
+            drive: DiffDrive::default(),
+            motion_noise: OdometryNoise::default(),
+            range_model: RangeModel::default(),
+            rng: StdRng::seed_from_u64(seed),
+            adaptive: None,
+            w_slow: 0.,
+            w_fast: 0.,
+            readings: 0,
+        };
+        t.particles = vec![Particle { pose: Vector3::new(0., 0., 0.), weight: 1. / count.max(1) as f64 }; count.max(1)];
+        t.reset(&RobotStartBelief::Uniform);
+        t
+    }
+
+    // count particles spread over the free cells of the robot's map.
+    pub fn from_robot<R: RobotAccess>(robot: &R, count: usize, seed: u64) -> Self {
+        ParticleFilter::new(GridMap::from_points(&robot.get_map()), robot.get_hcsr04_max_range(), count, seed)
+    }
+
+    pub fn set_beam_cone(&mut self, beam: BeamCone) {
+        self.beam = beam;
+    }
+
+    pub fn set_drive(&mut self, drive: DiffDrive) {
+        self.drive = drive;
+    }
+
+    pub fn set_motion_noise(&mut self, motion_noise: OdometryNoise) {
+        self.motion_noise = motion_noise;
+    }
+
+    pub fn set_range_model(&mut self, range_model: RangeModel) {
+        self.range_model = range_model;
+    }
+
+    // Switches between a fixed number of particles (None) and KLD sampling with random particles.
+    pub fn set_adaptive(&mut self, adaptive: Option<AdaptiveConfig>) {
+        self.adaptive = adaptive;
+        self.w_slow = 0.;
+        self.w_fast = 0.;
+        self.readings = 0;
+    }
+
+    // 1 / sum(w^2). Equals the number of particles if all weights are the same.
+    pub fn effective_sample_size(&self) -> f64 {
+        let sum: f64 = self.particles.iter().map(|p| p.weight.powi(2)).sum();
+        match sum > 0. {
+            true => 1. / sum,
+            false => 0.,
+        }
+    }
+
+    // Scales the weights to sum up to one. If all particles died we are lost and start over.
+    fn normalize(&mut self) {
+        let sum: f64 = self.particles.iter().map(|p| p.weight).sum();
+        if sum > 0. {
+            self.particles.iter_mut().for_each(|p| p.weight /= sum);
+        } else {
+            self.reset(&RobotStartBelief::Uniform);
+        }
+    }
+
+    // Low variance resampling (Probabilistic Robotics, table 4.4). One random number picks
+    // n evenly spaced points on the cumulative weights, so good particles survive in
+    // proportion to their weight without the extra noise of drawing n times.
+    fn resample(&mut self) {
+        let n = self.particles.len();
+        let step = 1. / n as f64;
+        let r = self.rng.gen_range(0. ..step);
+        let mut c = self.particles[0].weight;
+        let mut i = 0;
+        let mut new = Vec::with_capacity(n);
+        for m in 0..n {
+            let u = r + m as f64 * step;
+            while u > c && i < n - 1 {
+                i += 1;
+                c += self.particles[i].weight;
+            }
+            new.push(Particle { pose: self.particles[i].pose, weight: step });
+        }
+        self.particles = new;
+    }
+
+    // Probability to draw a random pose instead of a particle, 1 - w_fast / w_slow once the
+    // likelihood dropped far enough, else 0.
+    fn p_random(&self, a: AdaptiveConfig) -> f64 {
+        match self.w_fast < a.drop_ratio * self.w_slow {
+            true => (1. - self.w_fast / self.w_slow).max(0.),
+            false => 0.,
+        }
+    }
+
+    // KLD resampling with random particles. Draws from the weighted particles (or a random
+    // pose with probability p_random) until the KLD bound for the bins hit so far is met.
+    fn resample_adaptive(&mut self, a: AdaptiveConfig) {
+        let p_random = self.p_random(a);
+        let cumulative: Vec<f64> = self.particles
+            .iter()
+            .scan(0., |c, p| {
+                *c += p.weight;
+                Some(*c)
+            })
+            .collect();
+        let mut bins = HashSet::new();
+        let mut needed = a.min_particles;
+        let mut poses = Vec::new();
+        while poses.len() < a.max_particles && poses.len() < needed {
+            let pose = match self.rng.gen_bool(p_random) {
+                true => self.sample_free(),
+                false => {
+                    let u = self.rng.gen::<f64>() * cumulative[cumulative.len() - 1];
+                    let i = cumulative.partition_point(|c| *c < u).min(cumulative.len() - 1);
+                    self.particles[i].pose
+                },
+            };
+            if bins.insert(a.bin(pose)) {
+                needed = a.kld_bound(bins.len());
+            }
+            poses.push(pose);
+        }
+        let n = poses.len();
+        self.particles = poses.into_iter().map(|pose| Particle { pose, weight: 1. / n as f64 }).collect();
+    }
+
+    // Random pose in a random free cell.
+    fn sample_free(&mut self) -> Vector3<f64> {
+        if self.free.is_empty() {
+            return Vector3::new(0., 0., 0.);
+        }
+        let (i, j) = self.free[self.rng.gen_range(0..self.free.len())];
+        let corner = self.map.cell_center(i, j) - Vector2::new(0.5, 0.5);
+        Vector3::new(
+            corner.x + self.rng.gen::<f64>(),
+            corner.y + self.rng.gen::<f64>(),
+            self.rng.gen_range(-PI..PI),
+        )
+    }
+
+    fn sample_start(&mut self, start: &RobotStartBelief) -> Vector3<f64> {
+        match start {
+            RobotStartBelief::PointMass(p) => *p,
+            RobotStartBelief::Uniform => self.sample_free(),
+            RobotStartBelief::Gaussian { mean, covariance } => match covariance.cholesky() {
+                Some(chol) => {
+                    let z = Vector3::from_fn(|_, _| self.rng.sample::<f64, _>(StandardNormal));
+                    let mut p = mean + chol.l() * z;
+                    p.z = normalize_angle(p.z);
+                    p
+                },
+                None => *mean,
+            },
+            RobotStartBelief::Region { min, max, heading_range } => {
+                let width = match heading_range.1 - heading_range.0 >= TAU {
+                    true => TAU,
+                    false => (heading_range.1 - heading_range.0).rem_euclid(TAU),
+                };
+                let mut p = Vector3::new(min.x, min.y, heading_range.0);
+                for _ in 0..REGION_SAMPLE_TRIES {
+                    p = Vector3::new(
+                        min.x + (max.x - min.x) * self.rng.gen::<f64>(),
+                        min.y + (max.y - min.y) * self.rng.gen::<f64>(),
+                        normalize_angle(heading_range.0 + width * self.rng.gen::<f64>()),
+                    );
+                    if self.map.is_free(p.x, p.y) {
+                        break;
+                    }
+                }
+                p
+            },
+            RobotStartBelief::Hypotheses(h) => {
+                let total: f64 = h.iter().map(|(_, w)| w.max(0.)).sum();
+                if total <= 0. {
+                    return self.sample_free();
+                }
+                let mut u = self.rng.gen::<f64>() * total;
+                for (p, w) in h {
+                    u -= w.max(0.);
+                    if u <= 0. {
+                        return *p;
+                    }
+                }
+                h[h.len() - 1].0
+            },
+        }
+    }
+}
+
+impl Localizer for ParticleFilter {
+    fn name(&self) -> &'static str {
+        "Particle filter"
+    }
+
+    // Draws all particles from the start belief with equal weights. The adaptive filter starts
+    // with the most particles and drops the ones it doesn't need at the first resampling.
+    fn reset(&mut self, start: &RobotStartBelief) {
+        let n = match self.adaptive {
+            Some(a) => a.max_particles,
+            None => self.particles.len(),
+        };
+        self.w_slow = 0.;
+        self.w_fast = 0.;
+        self.readings = 0;
+        self.particles = (0..n)
+            .map(|_| Particle { pose: self.sample_start(start), weight: 1. / n as f64 })
+            .collect();
+    }
+
+    // Moves every particle by dt seconds with the wheel velocities plus motion noise.
+    // Particles that end up in a wall or outside of the map lose their weight.
+    fn predict(&mut self, left: f64, right: f64, dt: f64) {
+        for i in 0..self.particles.len() {
+            let pose = self.particles[i].pose;
+            let ideal = self.drive.integrate(pose, left, right, dt);
+            let next = self.motion_noise.sample(&mut self.rng, pose, ideal, pose);
+            self.particles[i].pose = next;
+            if !self.map.is_free(next.x, next.y) {
+                self.particles[i].weight = 0.;
+            }
+        }
+        self.normalize();
+    }
+
+    // Weights every particle by the likelihood of the reading and resamples when too few
+    // particles carry the weight. The adaptive filter also resamples to add random particles.
+    fn update(&mut self, measured: Option<f64>) {
+        for i in 0..self.particles.len() {
+            let expected = self.map.cone_hit(self.particles[i].pose, &self.beam, self.max_range).map(|h| h.dist);
+            self.particles[i].weight *= self.range_model.likelihood(expected, measured, self.max_range);
+        }
+        // The weights summed up to one before, so this is the average likelihood. We divide by
+        // the likelihood of a perfect fit, otherwise a far wall or no echo at all would look
+        // like a drop next to the readings of a near wall.
+        let best = self.range_model.likelihood(measured, measured, self.max_range);
+        let w_avg = self.particles.iter().map(|p| p.weight).sum::<f64>() / best;
+        self.normalize();
+        match self.adaptive {
+            Some(a) => {
+                self.readings += 1;
+                let n = self.readings as f64;
+                self.w_slow += a.alpha_slow.max(1. / n) * (w_avg - self.w_slow);
+                self.w_fast += a.alpha_fast.max(1. / n) * (w_avg - self.w_fast);
+                if self.effective_sample_size() < self.particles.len() as f64 / 2. || self.p_random(a) > 0. {
+                    self.resample_adaptive(a);
+                }
+            },
+            None => {
+                if self.effective_sample_size() < self.particles.len() as f64 / 2. {
+                    self.resample();
+                }
+            },
+        }
+    }
+
+    // Weighted mean and covariance of the particles.
+    fn estimate(&self) -> (Vector3<f64>, Matrix3<f64>) {
+        let poses: Vec<(Vector3<f64>, f64)> = self.particles.iter().map(|p| (p.pose, p.weight)).collect();
+        weighted_moments(&poses)
+    }
+
+    fn samples(&self) -> Vec<(Vector2<f64>, f64)> {
+        self.particles.iter().map(|p| (p.pose.xy(), p.weight)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::small_map;
+    use crate::localization::fixtures::{small_sim, step};
+    use crate::robo::sim_pi::NoiseConfig;
+
+    #[test]
+    fn tracks_the_simulated_robot() {
+        let start = Vector3::new(2.5, 2.5, 0.3);
+        let mut sim = small_sim(NoiseConfig::default(), start);
+        let mut pf = ParticleFilter::from_robot(&sim, 200, 1);
+        pf.reset(&RobotStartBelief::PointMass(start));
+        sim.set_wheel_velo(8., 10.);
+        for _ in 0..30 {
+            step(&mut sim, &mut pf, 0.1);
+        }
+        let (mean, cov) = pf.estimate();
+        let truth = sim.robot_position();
+        assert!((mean.xy() - truth.xy()).norm() < 0.5, "{mean} vs {truth}");
+        assert!(normalize_angle(mean.z - truth.z).abs() < 0.3);
+        assert!(cov[(0, 0)] > 0. && cov[(0, 0)] < 0.25);
+    }
+
+    #[test]
+    fn adaptive_filter_keeps_the_start_pose() {
+        let start = Vector3::new(2.5, 2.5, 0.);
+        let mut sim = small_sim(NoiseConfig { seed: 3, ..NoiseConfig::default() }, start);
+        let mut pf = ParticleFilter::from_robot(&sim, 100, 3);
+        let config = AdaptiveConfig::default();
+        pf.set_adaptive(Some(config));
+        pf.reset(&RobotStartBelief::PointMass(start));
+        // Single bad readings on the way must not bring in random particles.
+        sim.set_wheel_velo(5., 6.);
+        for _ in 0..32 {
+            step(&mut sim, &mut pf, 0.125);
+            let (mean, _) = pf.estimate();
+            let truth = sim.robot_position();
+            assert!((mean.xy() - truth.xy()).norm() < 0.5, "{mean} vs {truth}");
+            assert_eq!(pf.p_random(config), 0.);
+        }
+        // Carried somewhere else, the readings stop fitting for good and they come in.
+        sim.set_robot_position(Vector3::new(10.5, 2.5, PI));
+        assert!((0..40).any(|_| {
+            step(&mut sim, &mut pf, 0.125);
+            pf.p_random(config) > 0.
+        }));
+    }
+
+    #[test]
+    fn kld_sampling_shrinks_when_localized() {
+        let mut pf = ParticleFilter::new(GridMap::from_points(&small_map()), 5., 100, 3);
+        let config = AdaptiveConfig { max_particles: 2000, ..AdaptiveConfig::default() };
+        pf.set_adaptive(Some(config));
+        pf.reset(&RobotStartBelief::Uniform);
+        assert_eq!(pf.particles.len(), 2000);
+        pf.resample_adaptive(config);
+        // Spread over the whole map the bound is not reached before max_particles.
+        assert_eq!(pf.particles.len(), 2000);
+
+        pf.reset(&RobotStartBelief::PointMass(Vector3::new(2.5, 2.5, 0.)));
+        pf.resample_adaptive(config);
+        assert_eq!(pf.particles.len(), config.min_particles);
+    }
+
+    #[test]
+    fn resampling_keeps_heavy_particles() {
+        let mut pf = ParticleFilter::new(GridMap::from_points(&[Vector2::new(0., 0.), Vector2::new(9., 9.)]), 5., 100, 2);
+        for (i, p) in pf.particles.iter_mut().enumerate() {
+            p.weight = if i == 7 { 1. } else { 0. };
+        }
+        pf.resample();
+        assert_eq!(pf.particles.len(), 100);
+        let pose = pf.particles[7].pose;
+        assert!(pf.particles.iter().all(|p| p.pose == pose && (p.weight - 0.01).abs() < 1e-12));
+    }
+}
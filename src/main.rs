@@ -2,17 +2,61 @@
 use rppal::system::DeviceInfo;
 // Imports for ratatui.
 use std::io;
+use std::time::Duration;
+// Imports for the command line.
+use clap::{CommandFactory, Parser};
 
 
 mod robo;
-use robo::MyPi;
+use robo::{MyPi, RobotAccess};
 
 mod app;
+mod grid;
+mod localization;
+use localization::Localizer;
+mod motor;
+mod encoder;
+mod ultrasonic;
+mod control;
+mod hardware;
+mod cli;
+mod config;
+mod tiled;
 
 // Consts for Hardware.
 const GPIO_LED: u8 = 24;
 const GPIO_US_TRIG: u8 = 17;
 const GPIO_US_ECHO: u8 = 27;
+// Motor driver (L298N or TB6612). GPIO 12 and 13 can also do hardware PWM.
+const GPIO_MOTOR_LEFT_PWM: u8 = 12;
+const GPIO_MOTOR_LEFT_IN1: u8 = 5;
+const GPIO_MOTOR_LEFT_IN2: u8 = 6;
+const GPIO_MOTOR_RIGHT_PWM: u8 = 13;
+const GPIO_MOTOR_RIGHT_IN1: u8 = 20;
+const GPIO_MOTOR_RIGHT_IN2: u8 = 21;
+// Standby pin of the TB6612, None for the L298N.
+const GPIO_MOTOR_STANDBY: Option<u8> = None;
+const MOTOR_PWM_FREQUENCY: f64 = 100.;
+const MOTOR_DEADBAND: f64 = 0.05;
+const MOTOR_MIN_DUTY: f64 = 0.3;
+// Wheel encoders. Set the B pins to None for single channel encoders.
+const GPIO_ENCODER_LEFT_A: u8 = 22;
+const GPIO_ENCODER_LEFT_B: Option<u8> = Some(23);
+const GPIO_ENCODER_RIGHT_A: u8 = 25;
+const GPIO_ENCODER_RIGHT_B: Option<u8> = Some(26);
+// Counted edges per wheel revolution (both edges of A).
+const ENCODER_TICKS_PER_REV: f64 = 40.;
+// Rate of the wheel speed control loop in Hz for the simulation. On the robot it comes from
+// the hardware profile.
+const CONTROL_RATE: f64 = 100.;
+
+// Defaults of the localization, a robot config can change them. The particle count is only
+// used without adaptive sampling.
+const PARTICLE_COUNT: usize = 500;
+
+// Max range of the sensors if the command line doesn't set one.
+const SIM_MAX_RANGE: f64 = 5.;
+const REAL_MAX_RANGE: f64 = 4.;
 
 
 // impl Widget for &App {
@@ -41,37 +85,187 @@ const GPIO_US_ECHO: u8 = 27;
 //     }
 // }
 
+// Sets up the real robot on the board of the profile.
+fn setup_real(profile: &hardware::HardwareProfile, config: &config::RobotConfig, args: &cli::Args, map: Vec<nalgebra::Vector2<f64>>, start: Option<nalgebra::Vector3<f64>>) -> Result<robo::real_pi::MyPiReal, Box<dyn std::error::Error>> {
+    // The config can wire the robot differently than our 40 pin header wiring.
+    let pins = config.pins.unwrap_or(hardware::PINS_40);
+    let max_range = args.max_range.or(config.sensor.max_range).unwrap_or(REAL_MAX_RANGE);
+    // We init the GPIO structures
+    let mut real = robo::real_pi::MyPiReal::new(pins.led, pins.us_trig, pins.us_echo, max_range)?;
+    real.set_drive(config.drive());
+    real.set_velo_limits(config.velocity.min, config.velocity.max);
+    // A reading and its retries must not hold up the next tick.
+    real.set_retry_policy(robo::RetryPolicy { budget: Duration::from_millis(args.tick_ms), ..robo::RetryPolicy::default() });
+    let motor_config = |left, right| motor::HBridgeConfig {
+        left: motor::MotorPins { speed: left, in1: pins.motor_left_in1, in2: pins.motor_left_in2, invert: false },
+        // The right motor is mounted mirrored.
+        right: motor::MotorPins { speed: right, in1: pins.motor_right_in1, in2: pins.motor_right_in2, invert: true },
+        standby: pins.motor_standby,
+        frequency: MOTOR_PWM_FREQUENCY,
+        deadband: MOTOR_DEADBAND,
+        min_duty: MOTOR_MIN_DUTY,
+    };
+    let software = motor_config(motor::SpeedPin::Software(pins.motor_left_pwm), motor::SpeedPin::Software(pins.motor_right_pwm));
+    // Hardware PWM needs both pwm pins on the channels of the overlay. If the pins are elsewhere
+    // or the overlay is not there, we take software PWM on the same pins.
+    let motors = match (hardware::pwm_channel(pins.motor_left_pwm), hardware::pwm_channel(pins.motor_right_pwm)) {
+        (Some(l), Some(r)) if l != r => motor::HBridge::new(motor_config(motor::SpeedPin::Hardware(l), motor::SpeedPin::Hardware(r)))
+            .or_else(|_| motor::HBridge::new(software)),
+        _ => motor::HBridge::new(software),
+    }?;
+    real.set_motor_driver(Box::new(motors));
+    let gpio = rppal::gpio::Gpio::new()?;
+    let left = encoder::Encoder::new(&gpio, encoder::EncoderConfig { a: pins.encoder_left_a, b: pins.encoder_left_b, ticks_per_rev: ENCODER_TICKS_PER_REV, invert: false })?;
+    let right = encoder::Encoder::new(&gpio, encoder::EncoderConfig { a: pins.encoder_right_a, b: pins.encoder_right_b, ticks_per_rev: ENCODER_TICKS_PER_REV, invert: true })?;
+    real.set_encoders(left, right);
+    real.enable_speed_control(control::PidGains::default(), profile.control_rate);
+    // The robot drives in the same world the app shows.
+    real.set_map(map);
+    if let Some(start) = start {
+        real.set_robot_position(start);
+    }
+    Ok(real)
+}
+
+// Sets up the simulated robot on the map of the command line.
+fn setup_sim(config: &config::RobotConfig, args: &cli::Args, map: Vec<nalgebra::Vector2<f64>>, start: Option<nalgebra::Vector3<f64>>) -> (MyPi, robo::RobotStartBelief) {
+    let noise = robo::sim_pi::NoiseConfig { seed: args.seed.unwrap_or(config.noise.sensor.seed), ..config.noise.sensor };
+    let max_range = args.max_range.or(config.sensor.max_range).unwrap_or(SIM_MAX_RANGE);
+    let mut sim = robo::sim_pi::MyPiSim::new(max_range, map, noise);
+    sim.set_drive(config.drive());
+    sim.set_beam_cone(config.beam());
+    sim.set_odometry_noise(config.noise.odometry);
+    sim.set_robot_radius(config.geometry.robot_radius);
+    sim.set_velo_limits(config.velocity.min, config.velocity.max);
+    // Without a start pose we start in a free cell in the corner of the small map.
+    let start = start.unwrap_or(nalgebra::Vector3::new(2.5, 2.5, 0.));
+    sim.set_robot_position(start);
+    // Same speed control as on the robot, so the gains can be tuned here.
+    sim.enable_speed_control(control::MotorModel::default(), control::PidGains::default(), CONTROL_RATE);
+    (MyPi::Sim(sim), robo::RobotStartBelief::PointMass(start))
+}
+
+// Stops with a usage error, like clap does for flags it can't parse.
+fn usage_error(message: String) -> ! {
+    cli::Args::command().error(clap::error::ErrorKind::ValueValidation, message).exit()
+}
+
 fn main() -> io::Result<()> {
+    let args = cli::Args::parse();
+    let world = args.world().unwrap_or_else(|e| usage_error(e));
+    let map = world.wall_list.clone();
+    // The command line wins over the start markers of the map. The simulation starts on the
+    // first marker, the real robot only if we are told which one it stands on.
+    let marker = world.start(args.start_at.as_deref()).map(|m| m.pose);
+    let sim_start = args.start.or(marker);
+    let real_start = args.start.or(marker.filter(|_| args.start_at.is_some()));
+    // A broken config stops us right here, we don't want to drive a robot with half of it.
+    let config = match &args.config {
+        Some(path) => config::RobotConfig::load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        }),
+        None => config::RobotConfig::default(),
+    };
+    // The robot has to fit where it starts, not only its center but everything in its radius.
+    if let Some(start) = sim_start {
+        let grid = grid::GridMap::from_points(&map);
+        if let Some(wall) = grid.footprint_collision(start.xy(), config.geometry.robot_radius) {
+            usage_error(format!(
+                "the start pose ({}, {}) is too close to the wall at ({}, {}) for a robot with radius {}",
+                start.x, start.y, wall.x, wall.y, config.geometry.robot_radius,
+            ));
+        }
+    }
     //  -----------------------------------------------
     // Here we start with the Hardward setup.
-    // Here we check if we are running on a raspberry Pi or a something else.
+    // Here we check if we are running on a raspberry Pi or a something else, unless the
+    // command line forces the simulation.
     // Either way we get a MyPi object.
-    let mut my_pi = match DeviceInfo::new() 
-    {
-        Ok(di) => 
-            if di.model() == rppal::system::Model::RaspberryPi4B
-            {
-                // Now we know we are on a Raspberry Pi 4B.
-                 println!("Device ID: {}.", di.model()); 
-                // We init the GPIO structures
-                MyPi::Real(robo::real_pi::MyPiReal::new(GPIO_LED, GPIO_US_TRIG, GPIO_US_ECHO, 4. ))
-                
-            }
-            else  
-            {
-                panic!("Unknown Raspberry Pi -> check if adjustments need to be made!")
+    let device = match args.sim {
+        true => None,
+        false => DeviceInfo::new().ok(),
+    };
+    let (my_pi, start_belief) = match device {
+        Some(di) => match hardware::profile(di.model()) {
+            Some(profile) => {
+                // Now we know which Raspberry Pi we are on and what it can do.
+                println!("Device ID: {} ({}).", di.model(), profile.name);
+                // We don't know where the robot stands, unless we are told. If the map has
+                // start markers, it stands on one of them.
+                let markers: Vec<_> = world.markers_of(app::MarkerKind::Start).map(|m| (m.pose, 1.)).collect();
+                let belief = match real_start {
+                    Some(start) => robo::RobotStartBelief::PointMass(start),
+                    None if !markers.is_empty() => robo::RobotStartBelief::Hypotheses(markers),
+                    None => robo::RobotStartBelief::Uniform,
+                };
+                // Like a broken config, before the terminal UI takes over the screen.
+                let real = setup_real(profile, &config, &args, map.clone(), real_start).unwrap_or_else(|e| {
+                    eprintln!("Can't set up the robot: {}", e);
+                    std::process::exit(1)
+                });
+                (MyPi::Real(real), belief)
             },
-        Err(_) => {
-            // Here we know that we are not on a Raspberry Pi-
-            // Thus we return the Simulated Pi,
-            let sim = robo::sim_pi::MyPiSim::new(5., app::WALL_SMALL.to_vec().iter().map(|(x,y)| nalgebra::Vector2::new(*x,*y)).collect::<Vec<nalgebra::Vector2<f64>>>());
-            MyPi::Sim(sim)
-        }
+            None if args.real => usage_error(format!("no hardware profile for {}", di.model())),
+            None => {
+                // No profile for this board, but the simulation still runs.
+                eprintln!("No hardware profile for {}, running the simulation.", di.model());
+                setup_sim(&config, &args, map.clone(), sim_start)
+            },
+        },
+        None if args.real => usage_error("--real needs a Raspberry Pi".to_string()),
+        // Here we know that we are not on a Raspberry Pi-
+        // Thus we return the Simulated Pi,
+        None => setup_sim(&config, &args, map.clone(), sim_start),
     };
+    // A start region from the command line replaces the start pose of the localizers.
+    let start_belief = match args.start_region {
+        Some((min, max)) => robo::RobotStartBelief::Region { min, max, heading_range: (-std::f64::consts::PI, std::f64::consts::PI) },
+        None => start_belief,
+    };
+    //  -----------------------------------------------
+    // Here we set up the localization. It only needs the map and the sensor of the robot.
+    // The first one is active at the start, the app can switch to the others.
+    let seed = args.seed.unwrap_or(config.noise.sensor.seed);
+    let mut particle_filter = localization::particle::ParticleFilter::from_robot(&my_pi, config.filter.particle_count, seed);
+    if config.filter.adaptive {
+        particle_filter.set_adaptive(Some(localization::particle::AdaptiveConfig::default()));
+    }
+    particle_filter.set_beam_cone(config.beam());
+    particle_filter.set_drive(config.drive());
+    particle_filter.set_motion_noise(config.noise.odometry);
+    particle_filter.set_range_model(config.range_model());
+    let mut histogram = localization::histogram::HistogramFilter::from_robot(&my_pi);
+    histogram.set_beam_cone(config.beam());
+    histogram.set_drive(config.drive());
+    histogram.set_range_model(config.range_model());
+    histogram.set_motion_noise(config.noise.odometry);
+    if let Some(bins) = config.filter.heading_bins {
+        histogram.set_heading_bins(bins);
+    }
+    let mut ekf = localization::ekf::Ekf::from_robot(&my_pi, nalgebra::Vector3::zeros(), nalgebra::Matrix3::identity());
+    ekf.set_beam_cone(config.beam());
+    ekf.set_drive(config.drive());
+    ekf.set_range_model(config.range_model());
+    ekf.set_motion_noise(config.noise.odometry);
+    let mut localizers: Vec<Box<dyn Localizer>> = vec![
+        Box::new(particle_filter),
+        Box::new(histogram),
+        Box::new(ekf),
+    ];
+    for l in localizers.iter_mut() {
+        l.reset(&start_belief);
+    }
+    let mut app = app::App::new(my_pi, localizers, world);
+    app.set_tick_rate(Duration::from_millis(args.tick_ms));
+    if args.headless {
+        app.run_headless(Duration::from_secs_f64(args.duration), args.velo);
+        return Ok(());
+    }
     //  -----------------------------------------------
     // Here we start with the setup of the terminal UI.
     let mut terminal = ratatui::init();
-    let app_result = app::App::new(my_pi).run(&mut terminal);
+    let app_result = app.run(&mut terminal);
     ratatui::restore();
     app_result
 }
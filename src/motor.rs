@@ -0,0 +1,175 @@
+use rppal::gpio::{Gpio, OutputPin};
+use rppal::pwm::{Channel, Polarity, Pwm};
+
+use crate::robo::real_pi::MotorDriver;
+
+// Driver for two DC motors on an H-bridge like the L298N or the TB6612.
+// Each motor has a speed input that gets the PWM and two direction inputs:
+// IN1 high and IN2 low drives forward, the other way round backwards and both low lets it coast.
+
+// Where the PWM for the speed comes from. Software PWM works on every pin but jitters a bit,
+// hardware PWM needs one of the PWM pins (GPIO 12/13/18/19) and the pwm overlay.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedPin {
+    Software(u8),
+    Hardware(Channel),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotorPins {
+    pub speed: SpeedPin,
+    pub in1: u8,
+    pub in2: u8,
+    // Swaps forward and backward, for the motor that is mounted mirrored.
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HBridgeConfig {
+    pub left: MotorPins,
+    pub right: MotorPins,
+    // Standby pin of the TB6612, held high while the driver lives. The L298N has none.
+    pub standby: Option<u8>,
+    // PWM frequency in Hz.
+    pub frequency: f64,
+    // Duty cycles below this are treated as 0, the motor would only hum.
+    pub deadband: f64,
+    // Smallest duty cycle that still turns the wheel. Everything above the deadband is
+    // scaled into min_duty..=1.
+    pub min_duty: f64,
+}
+
+// Turns the requested duty cycle (-1..=1) into the one for the motor with deadband and min_duty.
+pub fn shape_duty(duty: f64, deadband: f64, min_duty: f64) -> f64 {
+    let d = duty.clamp(-1., 1.);
+    if d.abs() < deadband || d == 0. {
+        return 0.;
+    }
+    // Map deadband..1 onto min_duty..1 so there is no jump at the top.
+    let scaled = min_duty + (1. - min_duty) * (d.abs() - deadband) / (1. - deadband).max(f64::EPSILON);
+    scaled.clamp(0., 1.) * d.signum()
+}
+
+#[derive(Debug)]
+enum SpeedOutput {
+    Software(OutputPin, f64),
+    Hardware(Pwm),
+}
+
+impl SpeedOutput {
+    // A failed PWM update keeps the old speed.
+    fn set(&mut self, duty: f64) -> Result<(), String> {
+        match self {
+            SpeedOutput::Software(pin, frequency) => pin.set_pwm_frequency(*frequency, duty).map_err(|e| e.to_string()),
+            SpeedOutput::Hardware(pwm) => pwm.set_duty_cycle(duty).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Motor {
+    speed: SpeedOutput,
+    in1: OutputPin,
+    in2: OutputPin,
+    invert: bool,
+}
+
+impl Motor {
+    fn new(gpio: &Gpio, pins: MotorPins, frequency: f64) -> Result<Self, Box<dyn std::error::Error>> {
+        let speed = match pins.speed {
+            SpeedPin::Software(p) => {
+                let mut pin = gpio.get(p)?.into_output_low();
+                pin.set_pwm_frequency(frequency, 0.)?;
+                SpeedOutput::Software(pin, frequency)
+            },
+            SpeedPin::Hardware(channel) => SpeedOutput::Hardware(Pwm::with_frequency(channel, frequency, 0., Polarity::Normal, true)?),
+        };
+        Ok(Motor {
+            speed,
+            in1: gpio.get(pins.in1)?.into_output_low(),
+            in2: gpio.get(pins.in2)?.into_output_low(),
+            invert: pins.invert,
+        })
+    }
+
+    fn set(&mut self, duty: f64) -> Result<(), String> {
+        let duty = if self.invert { -duty } else { duty };
+        if duty > 0. {
+            self.in2.set_low();
+            self.in1.set_high();
+        } else if duty < 0. {
+            self.in1.set_low();
+            self.in2.set_high();
+        } else {
+            self.in1.set_low();
+            self.in2.set_low();
+        }
+        self.speed.set(duty.abs())
+    }
+}
+
+#[derive(Debug)]
+pub struct HBridge {
+    left: Motor,
+    right: Motor,
+    standby: Option<OutputPin>,
+    deadband: f64,
+    min_duty: f64,
+}
+
+impl HBridge {
+    pub fn new(config: HBridgeConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let gpio = Gpio::new()?;
+        let standby = match config.standby {
+            Some(p) => {
+                let mut pin = gpio.get(p)?.into_output_low();
+                pin.set_high();
+                Some(pin)
+            },
+            None => None,
+        };
+        Ok(HBridge {
+            left: Motor::new(&gpio, config.left, config.frequency)?,
+            right: Motor::new(&gpio, config.right, config.frequency)?,
+            standby,
+            deadband: config.deadband,
+            min_duty: config.min_duty,
+        })
+    }
+}
+
+impl MotorDriver for HBridge {
+    // The right motor is set even if the left one fails.
+    fn set_duty(&mut self, left: f64, right: f64) -> Result<(), String> {
+        let l = self.left.set(shape_duty(left, self.deadband, self.min_duty));
+        let r = self.right.set(shape_duty(right, self.deadband, self.min_duty));
+        l.and(r)
+    }
+}
+
+// Stop the motors and put the TB6612 to sleep.
+impl Drop for HBridge {
+    fn drop(&mut self) {
+        let _ = self.stop();
+        if let Some(pin) = self.standby.as_mut() {
+            pin.set_low();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duty_has_deadband_and_min_duty() {
+        assert_eq!(shape_duty(0.04, 0.05, 0.3), 0.);
+        assert_eq!(shape_duty(-0.04, 0.05, 0.3), 0.);
+        assert!((shape_duty(0.05, 0.05, 0.3) - 0.3).abs() < 1e-12);
+        assert!((shape_duty(-0.05, 0.05, 0.3) + 0.3).abs() < 1e-12);
+        assert_eq!(shape_duty(1., 0.05, 0.3), 1.);
+        assert_eq!(shape_duty(-2., 0.05, 0.3), -1.);
+        // Without deadband and min duty it passes through.
+        assert_eq!(shape_duty(0.5, 0., 0.), 0.5);
+    }
+}
@@ -1,109 +1,561 @@
 use core::f64;
 
-use nalgebra::{Vector2, Vector3};
+use nalgebra::{Matrix3, Vector2, Vector3};
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::Deserialize;
 
 
 extern crate nalgebra as na;
 
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum RobotStartBelief {
     // Startposition is as point mass at the given location.
     PointMass(Vector3<f64>),
+    // We know nothing, every free position and heading is equally likely (global localization).
+    Uniform,
+    // Normal distribution around mean (x, y, theta).
+    Gaussian { mean: Vector3<f64>, covariance: Matrix3<f64> },
+    // Somewhere in the box between min and max with a heading between
+    // heading_range.0 and heading_range.1, going counter clockwise.
+    Region { min: Vector2<f64>, max: Vector2<f64>, heading_range: (f64, f64) },
+    // A list of possible poses, each with a weight. The weights don't need to sum up to one.
+    Hypotheses(Vec<(Vector3<f64>, f64)>),
+}
+
+impl RobotStartBelief {
+    // Unnormalized density of the belief at pose. Point masses have no density and give 0.
+    pub fn density(&self, pose: Vector3<f64>) -> f64 {
+        match self {
+            RobotStartBelief::PointMass(_) | RobotStartBelief::Hypotheses(_) => 0.,
+            RobotStartBelief::Uniform => 1.,
+            RobotStartBelief::Gaussian { mean, covariance } => {
+                let mut d = pose - mean;
+                d.z = normalize_angle(d.z);
+                match covariance.try_inverse() {
+                    Some(inv) => (-0.5 * (d.transpose() * inv * d)[(0, 0)]).exp(),
+                    None => 0.,
+                }
+            },
+            RobotStartBelief::Region { min, max, heading_range } => {
+                let in_box = pose.x >= min.x && pose.x <= max.x && pose.y >= min.y && pose.y <= max.y;
+                match in_box && heading_in_range(pose.z, *heading_range) {
+                    true => 1.,
+                    false => 0.,
+                }
+            },
+        }
+    }
+}
+
+// True if the angle a lies in the range going counter clockwise from range.0 to range.1.
+// A range of a full turn or more contains every angle.
+pub fn heading_in_range(a: f64, range: (f64, f64)) -> bool {
+    let width = range.1 - range.0;
+    if width >= f64::consts::TAU {
+        return true;
+    }
+    (a - range.0).rem_euclid(f64::consts::TAU) <= width.rem_euclid(f64::consts::TAU)
+}
+
+// Geometry of a differential drive robot. Wheel velocities are angular velocities in rad/s,
+// lengths are in map units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffDrive {
+    // Distance between the two wheels.
+    pub wheel_base: f64,
+    pub wheel_radius: f64,
+}
+
+impl Default for DiffDrive {
+    fn default() -> Self {
+        DiffDrive { wheel_base: 0.5, wheel_radius: 0.1 }
+    }
+}
+
+impl DiffDrive {
+    // Translational and rotational velocity (v, w) of the robot for the given wheel velocities.
+    pub fn twist(&self, left: f64, right: f64) -> (f64, f64) {
+        let vl = left * self.wheel_radius;
+        let vr = right * self.wheel_radius;
+        ((vr + vl) / 2., (vr - vl) / self.wheel_base)
+    }
+
+    // Moves the pose (x, y, theta) forward by dt seconds with constant wheel velocities.
+    // The robot drives on a circular arc, so this is exact and not an euler step.
+    pub fn integrate(&self, pose: Vector3<f64>, left: f64, right: f64, dt: f64) -> Vector3<f64> {
+        let (v, w) = self.twist(left, right);
+        let theta = pose.z;
+        if w.abs() < 1e-9 {
+            return Vector3::new(pose.x + v * dt * theta.cos(), pose.y + v * dt * theta.sin(), theta);
+        }
+        let r = v / w;
+        let theta_new = theta + w * dt;
+        Vector3::new(
+            pose.x + r * (theta_new.sin() - theta.sin()),
+            pose.y - r * (theta_new.cos() - theta.cos()),
+            normalize_angle(theta_new),
+        )
+    }
+}
+
+// Error parameters of the odometry motion model (Probabilistic Robotics, table 5.6).
+// alpha1: rotation error from rotation, alpha2: rotation error from translation,
+// alpha3: translation error from translation, alpha4: translation error from rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OdometryNoise {
+    pub alpha1: f64,
+    pub alpha2: f64,
+    pub alpha3: f64,
+    pub alpha4: f64,
+}
+
+impl Default for OdometryNoise {
+    fn default() -> Self {
+        OdometryNoise { alpha1: 0.05, alpha2: 0.01, alpha3: 0.05, alpha4: 0.01 }
+    }
+}
+
+impl OdometryNoise {
+    #[cfg(test)]
+    pub fn none() -> Self {
+        OdometryNoise { alpha1: 0., alpha2: 0., alpha3: 0., alpha4: 0. }
+    }
+
+    // Takes the motion between the two odometry poses from and to, adds noise
+    // and applies it to pose.
+    pub fn sample<R: Rng>(&self, rng: &mut R, from: Vector3<f64>, to: Vector3<f64>, pose: Vector3<f64>) -> Vector3<f64> {
+        let (rot1, trans, rot2) = odometry_deltas(from, to);
+        let rot1_hat = rot1 - sample_normal(rng, self.alpha1 * rot1.powi(2) + self.alpha2 * trans.powi(2));
+        let trans_hat = trans - sample_normal(rng, self.alpha3 * trans.powi(2) + self.alpha4 * (rot1.powi(2) + rot2.powi(2)));
+        let rot2_hat = rot2 - sample_normal(rng, self.alpha1 * rot2.powi(2) + self.alpha2 * trans.powi(2));
+        Vector3::new(
+            pose.x + trans_hat * (pose.z + rot1_hat).cos(),
+            pose.y + trans_hat * (pose.z + rot1_hat).sin(),
+            normalize_angle(pose.z + rot1_hat + rot2_hat),
+        )
+    }
+}
+
+// Splits the motion between two poses into a rotation, a straight line and a second rotation.
+// Driving backwards gives a negative translation instead of two half turns.
+pub fn odometry_deltas(from: Vector3<f64>, to: Vector3<f64>) -> (f64, f64, f64) {
+    let d = Vector2::new(to.x - from.x, to.y - from.y);
+    let mut trans = d.norm();
+    let mut rot1 = 0.;
+    if trans > 1e-9 {
+        let heading = Vector2::new(from.z.cos(), from.z.sin());
+        if d.dot(&heading) < 0. {
+            trans = -trans;
+            rot1 = normalize_angle((-d.y).atan2(-d.x) - from.z);
+        } else {
+            rot1 = normalize_angle(d.y.atan2(d.x) - from.z);
+        }
+    }
+    let rot2 = normalize_angle(to.z - from.z - rot1);
+    (rot1, trans, rot2)
+}
+
+// Zero mean normal sample with the given variance.
+pub fn sample_normal<R: Rng>(rng: &mut R, variance: f64) -> f64 {
+    match Normal::new(0., variance.sqrt()) {
+        Ok(n) if variance > 0. => n.sample(rng),
+        _ => 0.,
+    }
+}
+
+// Wraps an angle into [-pi, pi).
+pub fn normalize_angle(a: f64) -> f64 {
+    (a + f64::consts::PI).rem_euclid(f64::consts::TAU) - f64::consts::PI
+}
+
+// A bumper event: the robot touched a wall. pose is where the robot stopped and
+// wall is the lower left corner of the wall cell that was touched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collision {
+    pub pose: Vector3<f64>,
+    pub wall: Vector2<f64>,
+}
+
+// Why a distance reading failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SensorError {
+    // The measurement took longer than we are willing to wait.
+    Timeout,
+    // The GPIO access or the driver failed.
+    Gpio(String),
+    // The sensor returned a distance that can't be real (negative, NaN or far above the max range).
+    OutOfRange(f64),
+}
+
+impl std::fmt::Display for SensorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SensorError::Timeout => write!(f, "timeout"),
+            SensorError::Gpio(e) => write!(f, "gpio error: {}", e),
+            SensorError::OutOfRange(d) => write!(f, "out of range: {:.2}", d),
+        }
+    }
+}
+
+impl std::error::Error for SensorError {}
+
+// How often a failed reading is tried again. The wait between the tries starts at delay and
+// grows by factor every time. All tries together stay within budget: we only try again if the
+// wait and another try as long as the last one still fit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub retries: usize,
+    pub delay: std::time::Duration,
+    pub factor: f64,
+    pub budget: std::time::Duration,
+}
+
+impl Default for RetryPolicy {
+    // Fits into the default tick of the app, 125 ms.
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 2,
+            delay: std::time::Duration::from_millis(5),
+            factor: 2.,
+            budget: std::time::Duration::from_millis(125),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Calls f until it succeeds or the retries are used up and returns the last result.
+    pub fn run<T, E>(&self, mut f: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+        let start = std::time::Instant::now();
+        let mut delay = self.delay;
+        let mut res = f();
+        let mut last = start.elapsed();
+        for _ in 0..self.retries {
+            if res.is_ok() || start.elapsed() + delay + last > self.budget {
+                break;
+            }
+            std::thread::sleep(delay);
+            delay = delay.mul_f64(self.factor);
+            let t = std::time::Instant::now();
+            res = f();
+            last = t.elapsed();
+        }
+        res
+    }
 }
 
 pub trait RobotAccess {
     // Stuff for utrasonic sensor. This sensor point straigth ahead.
-    fn get_hcsr04_dist(&mut self) -> Option<f64>;
+    // Ok(None) means no echo, nothing in range.
+    fn get_hcsr04_dist(&mut self) -> Result<Option<f64>, SensorError>;
     fn get_hcsr04_max_range(&self) -> f64;
     // Stuff for localization.
     fn get_map(&self) -> Vec<Vector2<f64>>;
     fn set_map(&mut self,map: Vec<Vector2<f64>>);
     //
     fn set_robot_position(&mut self, real_robot_position: Vector3<f64>);
-    fn set_robot_belief(&mut self, robot_start_belief: RobotStartBelief);
+    // The real pose. Only the simulation knows it, the localization has to estimate it.
     fn robot_position(&self) -> Vector3<f64>;
+    // Pose integrated from the wheel motion only. This drifts away from the real pose.
+    fn odometry_position(&self) -> Vector3<f64>;
     // wheel velocity max and min for both sides.
     fn wheel_velo_max(&self) -> f64;
     fn wheel_velo_min(&self) -> f64;
     fn set_wheel_velo(&mut self, left: f64, right: f64);
     fn get_wheel_velo(&self) -> (f64, f64);
+    // Wheel velocities that were really driven during the last step, e.g. from the encoders.
+    // These are what the localization should predict with.
+    fn get_measured_wheel_velo(&self) -> (f64, f64);
+    // Called by the app every tick with the elapsed time in seconds.
+    fn step(&mut self, dt: f64);
+    // Returns the collision since the last call, if there was one.
+    fn take_collision(&mut self) -> Option<Collision>;
+    // Number of motor commands that failed since the last call and the last of these errors.
+    fn take_motor_errors(&mut self) -> (usize, Option<String>);
 }
 
 pub mod real_pi {
-    use hc_sr04::{HcSr04, Unit};
+    use nalgebra::{Vector2, Vector3};
     use rppal::gpio::Gpio;
+
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{Collision, DiffDrive, RetryPolicy, SensorError};
+    use crate::control::{PidGains, SpeedController, WheelPlant};
+    use crate::encoder::Encoder;
+    use crate::ultrasonic::Ultrasonic;
+    use crate::grid::GridMap;
+
+    // Something that turns the wheels. The duty cycle is in -1..=1, negative drives backwards.
+    pub trait MotorDriver: std::fmt::Debug + Send {
+        fn set_duty(&mut self, left: f64, right: f64) -> Result<(), String>;
+        fn stop(&mut self) -> Result<(), String> {
+            self.set_duty(0., 0.)
+        }
+    }
+
+    // Failed motor commands, shared with the speed control thread. The app can't print them,
+    // the terminal is in raw mode, so it fetches them with take_motor_errors.
+    #[derive(Debug, Default)]
+    struct MotorErrors {
+        count: usize,
+        last: Option<String>,
+    }
+
+    impl MotorErrors {
+        fn record(errors: &Mutex<MotorErrors>, res: Result<(), String>) {
+            if let Err(e) = res {
+                let mut errors = errors.lock().unwrap();
+                errors.count += 1;
+                errors.last = Some(e);
+            }
+        }
+    }
+
     #[derive(Debug)]
     pub struct MyPiReal {
         gpio_pin_led: rppal::gpio::OutputPin,
-        pgio_us_hcsr04: HcSr04,
+        ultrasonic: Ultrasonic,
         max_range: f64,
+        map: GridMap,
+        drive: DiffDrive,
+        // We can't see where the robot really is, so this is the pose integrated from the
+        // measured wheel velocities (or the commanded ones without encoders).
+        // It starts wherever set_robot_position puts it.
+        odometry_position: Vector3<f64>,
+        max_velo: f64,
+        min_velo: f64,
+        velo_left: f64,
+        velo_rigth: f64,
+        // None until a driver is attached, then the wheel velocities only move the estimate.
+        motors: Option<Box<dyn MotorDriver>>,
+        // Left and right wheel encoder. Without them we trust the commanded velocities.
+        encoders: Option<(Encoder, Encoder)>,
+        measured_left: f64,
+        measured_right: f64,
+        // Once running, the speed control owns the motors and the encoders.
+        speed_control: Option<SpeedController>,
+        motor_errors: Arc<Mutex<MotorErrors>>,
+        retry: RetryPolicy,
+        // Readings that take longer count as failed. The echo of 4 m is back after about 25 ms.
+        sensor_timeout: Duration,
+    }
+
+    // Motors and encoders together, for the speed control.
+    #[derive(Debug)]
+    struct RealWheels {
+        motors: Box<dyn MotorDriver>,
+        encoders: (Encoder, Encoder),
+        errors: Arc<Mutex<MotorErrors>>,
+    }
+
+    impl WheelPlant for RealWheels {
+        fn apply(&mut self, left: f64, right: f64) {
+            MotorErrors::record(&self.errors, self.motors.set_duty(left, right));
+            self.encoders.0.set_direction(left >= 0.);
+            self.encoders.1.set_direction(right >= 0.);
+        }
+
+        fn advance(&mut self, _dt: f64) -> (f64, f64) {
+            (self.encoders.0.take_angle(), self.encoders.1.take_angle())
+        }
     }
 
     impl MyPiReal {
-        pub fn new(gpio_led: u8, gpio_us_trig: u8, gpio_us_echo: u8, max_range: f64) -> Self {
-            MyPiReal { 
-                gpio_pin_led: Gpio::new().unwrap().get(gpio_led).unwrap().into_output(), 
-                pgio_us_hcsr04: HcSr04::new(gpio_us_trig, gpio_us_echo, None).unwrap(),
-                max_range
+        // Fails if the GPIO can't be opened or a pin is taken.
+        pub fn new(gpio_led: u8, gpio_us_trig: u8, gpio_us_echo: u8, max_range: f64) -> Result<Self, Box<dyn std::error::Error>> {
+            let gpio = Gpio::new()?;
+            Ok(MyPiReal { 
+                gpio_pin_led: gpio.get(gpio_led)?.into_output(), 
+                ultrasonic: Ultrasonic::new(&gpio, gpio_us_trig, gpio_us_echo)?,
+                max_range,
+                map: GridMap::from_points(&[]),
+                drive: DiffDrive::default(),
+                odometry_position: Vector3::new(0., 0., 0.),
+                max_velo: 10.,
+                min_velo: -10.,
+                velo_left: 0.,
+                velo_rigth: 0.,
+                motors: None,
+                encoders: None,
+                measured_left: 0.,
+                measured_right: 0.,
+                speed_control: None,
+                motor_errors: Arc::new(Mutex::new(MotorErrors::default())),
+                retry: RetryPolicy::default(),
+                sensor_timeout: Duration::from_millis(60),
+            })
+        }
+
+        // Starts the closed loop speed control at rate_hz. Needs the motors and the encoders,
+        // returns false if one of them is missing.
+        pub fn enable_speed_control(&mut self, gains: PidGains, rate_hz: f64) -> bool {
+            let (motors, encoders) = match (self.motors.take(), self.encoders.take()) {
+                (Some(m), Some(e)) => (m, e),
+                (m, e) => {
+                    self.motors = m;
+                    self.encoders = e;
+                    return false;
+                },
+            };
+            self.speed_control = Some(SpeedController::spawn(RealWheels { motors, encoders, errors: self.motor_errors.clone() }, gains, rate_hz));
+            self.set_wheel_velo_internal(self.velo_left, self.velo_rigth);
+            true
+        }
+
+        pub fn set_encoders(&mut self, left: Encoder, right: Encoder) {
+            self.encoders = Some((left, right));
+            self.set_wheel_velo_internal(self.velo_left, self.velo_rigth);
+        }
+
+        pub fn set_motor_driver(&mut self, motors: Box<dyn MotorDriver>) {
+            self.motors = Some(motors);
+            self.set_wheel_velo_internal(self.velo_left, self.velo_rigth);
+        }
+
+        pub fn set_drive(&mut self, drive: DiffDrive) {
+            self.drive = drive;
+        }
+
+        pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+            self.retry = retry;
+        }
+
+        // Sets the wheel velocity limits. min has to be negative or zero and max positive or zero.
+        pub fn set_velo_limits(&mut self, min: f64, max: f64) {
+            self.min_velo = min.min(0.);
+            self.max_velo = max.max(0.);
+            self.set_wheel_velo_internal(self.velo_left, self.velo_rigth);
+        }
+
+        // Clamps the velocities and hands them to the speed control, or without it straight
+        // to the motors as part of the top speed.
+        fn set_wheel_velo_internal(&mut self, left: f64, right: f64) {
+            self.velo_left = left.clamp(self.min_velo, self.max_velo);
+            self.velo_rigth = right.clamp(self.min_velo, self.max_velo);
+            if let Some(c) = self.speed_control.as_ref() {
+                c.set_setpoint(self.velo_left, self.velo_rigth);
+                return;
+            }
+            let duty = |v: f64| match v >= 0. {
+                true if self.max_velo > 0. => v / self.max_velo,
+                false if self.min_velo < 0. => -v / self.min_velo,
+                _ => 0.,
+            };
+            let (l, r) = (duty(self.velo_left), duty(self.velo_rigth));
+            if let Some(m) = self.motors.as_mut() {
+                MotorErrors::record(&self.motor_errors, m.set_duty(l, r));
+            }
+            if let Some((el, er)) = self.encoders.as_ref() {
+                el.set_direction(self.velo_left >= 0.);
+                er.set_direction(self.velo_rigth >= 0.);
+            }
+        }
+    }
+
+    // Don't let the robot run off when the app ends or panics.
+    impl Drop for MyPiReal {
+        fn drop(&mut self) {
+            if let Some(m) = self.motors.as_mut() {
+                let _ = m.stop();
             }
         }
     }
 
     impl super::RobotAccess for MyPiReal {
-        fn get_hcsr04_dist(&mut self) -> Option<f64> {
+        // Failed readings are tried again by the retry policy, the LED is on while we measure.
+        fn get_hcsr04_dist(&mut self) -> Result<Option<f64>, SensorError> {
             self.gpio_pin_led.set_high();
-            match self.pgio_us_hcsr04.measure_distance(hc_sr04::Unit::Meters) {
-                Ok(Some(dist)) => {
-                    self.gpio_pin_led.set_low();
-                    return Some(dist as f64)},
-                Ok(None) => {
-                    self.gpio_pin_led.set_low();
-                    return None}, // Out of Range.
-                Err(t) => {
-                    println!("{}",t);
-                    self.gpio_pin_led.set_low();
-                    panic!("Error in hcsr04 dist measurement")},
-            }
+            let (sensor, max_range, timeout) = (&mut self.ultrasonic, self.max_range, self.sensor_timeout);
+            let res = self.retry.run(|| {
+                let dist = sensor.measure(timeout).map_err(|e| SensorError::Gpio(e.to_string()))?;
+                match dist {
+                    // No echo in time, the sensor is unplugged or broken.
+                    None => Err(SensorError::Timeout),
+                    Some(d) if d.is_nan() || d < 0. || d > 2. * max_range => Err(SensorError::OutOfRange(d)),
+                    // A bit beyond the max range is no echo as far as the rest of the app is concerned.
+                    Some(d) if d > max_range => Ok(None),
+                    Some(d) => Ok(Some(d)),
+                }
+            });
+            self.gpio_pin_led.set_low();
+            res
         }
         
         fn get_hcsr04_max_range(&self) -> f64 {
             self.max_range as f64
         }
         
-        fn get_map(&self) -> Vec<nalgebra::Vector2<f64>> {
-            todo!()
+        // Returns the lower left corner of every wall cell, sorted by x and then y.
+        fn get_map(&self) -> Vec<Vector2<f64>> {
+            self.map.to_points()
         }
         
-        fn set_map(&mut self,map: Vec<nalgebra::Vector2<f64>>) {
-            todo!()
+        fn set_map(&mut self,map: Vec<Vector2<f64>>) {
+            self.map = GridMap::from_points(&map);
         }
         
-        fn set_robot_position(&mut self, real_robot_position: nalgebra::Vector3<f64>) {
-            todo!()
+        // We can't move the real robot by hand from here, so this only resets the estimate.
+        fn set_robot_position(&mut self, real_robot_position: Vector3<f64>) {
+            self.odometry_position = real_robot_position;
         }
         
-        fn set_robot_belief(&mut self, robot_start_belief: super::RobotStartBelief) {
-            todo!()
+        // There is no ground truth on the real robot, the best we have is the odometry.
+        fn robot_position(&self) -> Vector3<f64> {
+            self.odometry_position
         }
-        
-        fn robot_position(&self) -> nalgebra::Vector3<f64> {
-            todo!()
+
+        fn odometry_position(&self) -> Vector3<f64> {
+            self.odometry_position
         }
         
         fn wheel_velo_max(&self) -> f64 {
-            todo!()
+            self.max_velo
         }
         
         fn wheel_velo_min(&self) -> f64 {
-            todo!()
+            self.min_velo
         }
         
         fn set_wheel_velo(&mut self, left: f64, right: f64) {
-            todo!()
+            self.set_wheel_velo_internal(left, right);
         }
         
         fn get_wheel_velo(&self) -> (f64, f64) {
-            todo!()
+            (self.velo_left, self.velo_rigth)
+        }
+
+        fn get_measured_wheel_velo(&self) -> (f64, f64) {
+            (self.measured_left, self.measured_right)
+        }
+
+        // The real robot moves by itself, we only follow it with the encoder ticks since the
+        // last step. Without encoders we assume the wheels turn as commanded.
+        fn step(&mut self, dt: f64) {
+            let angles = match (self.speed_control.as_ref(), self.encoders.as_mut()) {
+                (Some(c), _) => Some(c.take_angles()),
+                (None, Some((el, er))) => Some((el.take_angle(), er.take_angle())),
+                (None, None) => None,
+            };
+            (self.measured_left, self.measured_right) = match angles {
+                Some((al, ar)) if dt > 0. => (al / dt, ar / dt),
+                Some(_) => (0., 0.),
+                None => (self.velo_left, self.velo_rigth),
+            };
+            self.odometry_position = self.drive.integrate(self.odometry_position, self.measured_left, self.measured_right, dt);
+        }
+
+        // There is no bumper on the real robot.
+        fn take_collision(&mut self) -> Option<Collision> {
+            None
+        }
+
+        fn take_motor_errors(&mut self) -> (usize, Option<String>) {
+            let errors = std::mem::take(&mut *self.motor_errors.lock().unwrap());
+            (errors.count, errors.last)
         }
     }
 }
@@ -111,103 +563,244 @@ pub mod real_pi {
 pub mod sim_pi {
     use nalgebra::{Vector2, Vector3};
 
+    use super::{Collision, DiffDrive, OdometryNoise};
+    use crate::control::{MotorModel, PidGains, SpeedController};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rand_distr::{Distribution, Normal};
+    use serde::Deserialize;
+
     use super::RobotAccess;
-    
-    #[derive(Debug,Clone,Copy, PartialEq, Eq)]
-    pub enum PositionType {
-        Wall,
-        Empty,
+    pub use crate::grid::{BeamCone, GridMap, RayHit};
+
+    // Parameters of the range error model. This is a mixture in the style of the beam model
+    // (Probabilistic Robotics, ch. 6.3): every reading is either a noisy hit, a short reading
+    // from something in front of the wall, a max range dropout or a random reading.
+    // On top of that walls that are hit at a steep angle can reflect the echo away (specular miss).
+    #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct NoiseConfig {
+        // Std deviation of the hit in m is sigma_hit + sigma_hit_per_m * distance.
+        pub sigma_hit: f64,
+        pub sigma_hit_per_m: f64,
+        // Probability and rate of an exponentially distributed short reading.
+        pub p_short: f64,
+        pub lambda_short: f64,
+        // Probability that no echo is received at all.
+        pub p_max: f64,
+        // Probability of a uniform random reading in [0, max_range).
+        pub p_rand: f64,
+        // Angle of incidence (rad, 0 = head on) above which the echo can be reflected away
+        // and the probability that this happens.
+        pub specular_angle: f64,
+        pub p_specular: f64,
+        // Seed for the random number generator, so runs can be repeated.
+        pub seed: u64,
+    }
+
+    impl Default for NoiseConfig {
+        fn default() -> Self {
+            NoiseConfig {
+                sigma_hit: 0.01,
+                sigma_hit_per_m: 0.01,
+                p_short: 0.05,
+                lambda_short: 1.,
+                p_max: 0.02,
+                p_rand: 0.01,
+                specular_angle: 60_f64.to_radians(),
+                p_specular: 0.5,
+                seed: 0,
+            }
+        }
+    }
+
+    impl NoiseConfig {
+        // No noise at all. The sensor returns the exact ray cast distance.
+        #[cfg(test)]
+        pub fn none() -> Self {
+            NoiseConfig {
+                sigma_hit: 0.,
+                sigma_hit_per_m: 0.,
+                p_short: 0.,
+                lambda_short: 1.,
+                p_max: 0.,
+                p_rand: 0.,
+                specular_angle: std::f64::consts::FRAC_PI_2,
+                p_specular: 0.,
+                seed: 0,
+            }
+        }
     }
 
     #[derive(Debug)]
     pub struct MyPiSim {
-        v: f64,
         max_range: f64,
-        map: Vec<Vec<PositionType>>,
+        beam: BeamCone,
+        noise: NoiseConfig,
+        rng: StdRng,
+        drive: DiffDrive,
+        odometry_noise: OdometryNoise,
+        // Pose the robot thinks it has from counting wheel turns.
+        odometry_position: Vector3<f64>,
+        map: GridMap,
         robot_position: Vector3<f64>,
-        belief: Vec<Vec<f64>>,
         max_velo: f64,
         min_velo: f64,
         velo_left: f64,
         velo_rigth: f64,
+        // Velocities that really moved the robot in the last step. Zero after a collision.
+        measured_velo: (f64, f64),
+        // Speed control against a motor model. Without it the wheels turn exactly as commanded.
+        speed_control: Option<SpeedController>,
+        // Radius of the round robot footprint.
+        robot_radius: f64,
+        collision: Option<Collision>,
     }
 
     impl MyPiSim {
-        pub fn new(max_range: f64, map: Vec<Vector2<f64>>) -> Self {
+        pub fn new(max_range: f64, map: Vec<Vector2<f64>>, noise: NoiseConfig) -> Self {
 
             let mut t = MyPiSim {
-                v: 0.,
                 max_range,
-                map: Vec::new(),
-                belief: Vec::new(),
+                beam: BeamCone::default(),
+                noise,
+                rng: StdRng::seed_from_u64(noise.seed),
+                drive: DiffDrive::default(),
+                odometry_noise: OdometryNoise::default(),
+                odometry_position: Vector3::new(0., 0., 0.),
+                map: GridMap::from_points(&[]),
                 robot_position: Vector3::new(0.,0., 0.),
                 max_velo: 10.,
                 min_velo: -10.,
                 velo_left: 0.,
                 velo_rigth: 0.,
+                measured_velo: (0., 0.),
+                speed_control: None,
+                robot_radius: 0.3,
+                collision: None,
             };
             
             t.set_map(map);
             t
         }
+
+        pub fn set_beam_cone(&mut self, beam: BeamCone) {
+            self.beam = beam;
+        }
+
+        pub fn set_drive(&mut self, drive: DiffDrive) {
+            self.drive = drive;
+        }
+
+        pub fn set_odometry_noise(&mut self, odometry_noise: OdometryNoise) {
+            self.odometry_noise = odometry_noise;
+        }
+
+        pub fn set_robot_radius(&mut self, robot_radius: f64) {
+            self.robot_radius = robot_radius;
+        }
+
+        // Sets the wheel velocity limits. min has to be negative or zero and max positive or zero.
+        pub fn set_velo_limits(&mut self, min: f64, max: f64) {
+            self.min_velo = min.min(0.);
+            self.max_velo = max.max(0.);
+            let (l, r) = (self.velo_left, self.velo_rigth);
+            self.set_wheel_velo(l, r);
+        }
+
+        // Returns the first wall cell that overlaps the round footprint of the robot at position p.
+        pub fn footprint_collision(&self, p: Vector2<f64>) -> Option<Vector2<f64>> {
+            self.map.footprint_collision(p, self.robot_radius)
+        }
+
+        // Runs the same speed control as the real robot against the motor model, so the
+        // wheels lag behind the commanded velocities like real ones.
+        pub fn enable_speed_control(&mut self, model: MotorModel, gains: PidGains, rate_hz: f64) {
+            let c = SpeedController::spawn(model, gains, rate_hz);
+            c.set_setpoint(self.velo_left, self.velo_rigth);
+            self.speed_control = Some(c);
+        }
+
+        // Sweeps the beam cone for a robot at pose and returns the nearest hit without noise.
+        pub fn expected_hit(&self, pose: Vector3<f64>) -> Option<RayHit> {
+            self.map.cone_hit(pose, &self.beam, self.max_range)
+        }
+
+        // Applies the noise model to the exact reading of the beam cone.
+        fn add_noise(&mut self, hit: Option<RayHit>) -> Option<f64> {
+            let n = self.noise;
+            let hit = match hit {
+                Some(hit) => hit,
+                // Nothing in range, but the sensor can still pick up a random echo.
+                None => {
+                    return match self.rng.gen_bool(n.p_rand.clamp(0., 1.)) {
+                        true => Some(self.rng.gen_range(0. ..self.max_range)),
+                        false => None,
+                    }
+                }
+            };
+            if hit.incidence > n.specular_angle && self.rng.gen_bool(n.p_specular.clamp(0., 1.)) {
+                return None;
+            }
+            let u: f64 = self.rng.gen();
+            if u < n.p_max {
+                None
+            } else if u < n.p_max + n.p_short {
+                // Sample an exponential truncated at the true distance (inverse cdf).
+                let scale = 1. - (-n.lambda_short * hit.dist).exp();
+                let u: f64 = self.rng.gen();
+                Some(-(1. - u * scale).ln() / n.lambda_short)
+            } else if u < n.p_max + n.p_short + n.p_rand {
+                Some(self.rng.gen_range(0. ..self.max_range))
+            } else {
+                let sigma = n.sigma_hit + n.sigma_hit_per_m * hit.dist;
+                let d = match Normal::new(hit.dist, sigma) {
+                    Ok(normal) if sigma > 0. => normal.sample(&mut self.rng),
+                    _ => hit.dist,
+                };
+                match d > self.max_range {
+                    true => None,
+                    false => Some(d.max(0.)),
+                }
+            }
+        }
     }
 
     impl super::RobotAccess for MyPiSim {
-        // Increase the value by one and return it. If it is larger than max_range set it to zero.
-        fn get_hcsr04_dist(&mut self) -> Option<f64> {
-            self.v += 0.2;
-            if self.v > self.max_range {
-                self.v = 0.;
-            }
-            return Some(self.v)
+        // Sweep the beam cone around the robot heading and return the noisy distance to the
+        // nearest wall. None if nothing is in range (same as the real sensor).
+        fn get_hcsr04_dist(&mut self) -> Result<Option<f64>, super::SensorError> {
+            let hit = self.expected_hit(self.robot_position);
+            Ok(self.add_noise(hit))
         }
         
         fn get_hcsr04_max_range(&self) -> f64 {
             self.max_range
         }
         
+        // Returns the lower left corner of every wall cell, sorted by x and then y.
         fn get_map(&self) -> Vec<nalgebra::Vector2<f64>> {
-            let mut r = 0;
-            let mut c = 0;
-            let mut v = Vec::new();
-            for row in &self.map {
-                for col in row {
-                    if *col == PositionType::Wall {
-                        v.push(Vector2::new(r as f64, c as f64));
-                    }
-                    c += 1;
-                }
-                r += 1;
-                c = 0;
-            }
-            v
+            self.map.to_points()
         }
         
+        // Builds the grid from a list of wall points.
         fn set_map(&mut self,map: Vec<nalgebra::Vector2<f64>>) {
-            let (minx,miny,maxx,maxy) = map.iter()
-                .fold((f64::MAX, f64::MAX, f64::MIN, f64::MIN), |mut acc, v| {
-                    acc.0 = acc.0.min(v.x);
-                    acc.1 = acc.1.min(v.y);
-                    acc.2 = acc.2.max(v.x);
-                    acc.3 = acc.3.max(v.y);
-                    acc
-            });
-            let row  = vec![PositionType::Empty; maxx.floor() as usize - minx.floor() as usize];
-            let m  = vec![row; maxy.floor() as usize - miny.floor() as usize];
-            self.map = m;
+            self.map = GridMap::from_points(&map);
         }
         
+        // Sets the real pose and resets the odometry to it.
         fn set_robot_position(&mut self, real_robot_position: nalgebra::Vector3<f64>) {
             self.robot_position = real_robot_position;
+            self.odometry_position = real_robot_position;
         }
         
-        fn set_robot_belief(&mut self, robot_start_belief: super::RobotStartBelief) {
-            todo!()
-        }
-        
+        // This is the ground truth that only the simulation knows.
         fn robot_position(&self) -> nalgebra::Vector3<f64> {
             self.robot_position
         }
+
+        fn odometry_position(&self) -> nalgebra::Vector3<f64> {
+            self.odometry_position
+        }
         
         fn wheel_velo_max(&self) -> f64 {
             self.max_velo
@@ -218,13 +811,226 @@ pub mod sim_pi {
         }
         
         fn set_wheel_velo(&mut self, left: f64, right: f64) {
-            self.velo_left = left;
-            self.velo_rigth = right;
+            self.velo_left = left.clamp(self.min_velo, self.max_velo);
+            self.velo_rigth = right.clamp(self.min_velo, self.max_velo);
+            if let Some(c) = self.speed_control.as_ref() {
+                c.set_setpoint(self.velo_left, self.velo_rigth);
+            }
         }
 
         fn get_wheel_velo(&self) -> (f64, f64) {
             (self.velo_left, self.velo_rigth)
         }
+
+        // The simulated encoders see the commanded motion, the slip only moves the real pose.
+        fn get_measured_wheel_velo(&self) -> (f64, f64) {
+            self.measured_velo
+        }
+
+        // Drive the simulated robot with the current wheel velocities.
+        // The odometry follows the wheel motion exactly (the commanded one, or the one of the
+        // motor model with speed control), while the real robot slips according to the odometry noise model.
+        // If the robot would run into a wall it stays where it is, stops and we record a collision.
+        fn step(&mut self, dt: f64) {
+            let (left, right) = match self.speed_control.as_ref() {
+                Some(c) if dt > 0. => {
+                    let (al, ar) = c.take_angles();
+                    (al / dt, ar / dt)
+                },
+                _ => (self.velo_left, self.velo_rigth),
+            };
+            let odometry = self.drive.integrate(self.odometry_position, left, right, dt);
+            let next = self.odometry_noise.sample(&mut self.rng, self.odometry_position, odometry, self.robot_position);
+            match self.footprint_collision(Vector2::new(next.x, next.y)) {
+                Some(wall) => {
+                    self.set_wheel_velo(0., 0.);
+                    self.measured_velo = (0., 0.);
+                    self.collision = Some(Collision { pose: self.robot_position, wall });
+                },
+                None => {
+                    self.robot_position = next;
+                    self.odometry_position = odometry;
+                    self.measured_velo = (left, right);
+                },
+            }
+        }
+
+        fn take_collision(&mut self) -> Option<Collision> {
+            self.collision.take()
+        }
+
+        // The simulated motors always do what they are told.
+        fn take_motor_errors(&mut self) -> (usize, Option<String>) {
+            (0, None)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::grid::PositionType;
+
+        // 5 x 5 cells, the 3 x 3 in the middle are free and walls are all around.
+        fn walled_box(noise: NoiseConfig) -> MyPiSim {
+            let walls = (0..5)
+                .flat_map(|i| (0..5).map(move |j| (i, j)))
+                .filter(|&(i, j)| i == 0 || j == 0 || i == 4 || j == 4)
+                .map(|(i, j)| Vector2::new(i as f64, j as f64))
+                .collect();
+            let mut s = MyPiSim::new(10., walls, noise);
+            s.odometry_noise = OdometryNoise::none();
+            s
+        }
+
+        #[test]
+        fn ray_hits_the_nearest_wall() {
+            let mut s = walled_box(NoiseConfig::none());
+            let origin = Vector2::new(1.5, 2.5);
+            let cast = |origin, angle| s.map.cast_ray(origin, angle, s.max_range).map(|h| h.dist);
+            assert_eq!(cast(origin, 0.), Some(2.5));
+            assert_eq!(cast(origin, std::f64::consts::PI), Some(0.5));
+            let diagonal = cast(Vector2::new(1.5, 1.5), std::f64::consts::FRAC_PI_4).unwrap();
+            assert!((diagonal - 2.5 * 2_f64.sqrt()).abs() < 1e-9, "{}", diagonal);
+            // The sensor looks along the heading of the robot.
+            s.set_robot_position(Vector3::new(1.5, 2.5, std::f64::consts::FRAC_PI_2));
+            assert_eq!(s.get_hcsr04_dist(), Ok(Some(1.5)));
+            // Walls behind the max range give no echo.
+            s.max_range = 1.;
+            assert_eq!(s.get_hcsr04_dist(), Ok(None));
+        }
+
+        #[test]
+        fn beam_cone_reports_the_nearest_echo() {
+            assert_eq!(BeamCone { width: 1., rays: 3, ..BeamCone::default() }.ray_offsets(), vec![-0.5, 0., 0.5]);
+            let mut s = walled_box(NoiseConfig::none());
+            // A wall cell to the left of the center line.
+            let mut walls = s.get_map();
+            walls.push(Vector2::new(3., 3.));
+            s.set_map(walls);
+            s.set_robot_position(Vector3::new(1.5, 2.5, 0.));
+            s.set_beam_cone(BeamCone { width: 0., rays: 1, ..BeamCone::default() });
+            assert_eq!(s.get_hcsr04_dist(), Ok(Some(2.5)));
+            // The ray 20 degree to the left hits it first.
+            s.set_beam_cone(BeamCone { width: 40_f64.to_radians(), rays: 5, ..BeamCone::default() });
+            let d = s.get_hcsr04_dist().unwrap().unwrap();
+            assert!((d - 1.5 / 20_f64.to_radians().cos()).abs() < 1e-9, "{}", d);
+        }
+
+        #[test]
+        fn same_seed_gives_same_readings() {
+            let readings = |seed| {
+                let mut s = walled_box(NoiseConfig { seed, ..NoiseConfig::default() });
+                s.set_robot_position(Vector3::new(1.5, 2.5, 0.));
+                (0..50).map(|_| s.get_hcsr04_dist()).collect::<Vec<_>>()
+            };
+            assert_eq!(readings(7), readings(7));
+            assert_ne!(readings(7), readings(8));
+        }
+
+        #[test]
+        fn step_drives_with_clamped_wheel_velocities() {
+            let mut s = walled_box(NoiseConfig::none());
+            s.set_robot_position(Vector3::new(1.5, 2.5, 0.));
+            s.set_wheel_velo(20., 20.);
+            assert_eq!(s.get_wheel_velo(), (10., 10.));
+            s.step(1.);
+            assert!((s.robot_position() - Vector3::new(2.5, 2.5, 0.)).norm() < 1e-9);
+        }
+
+        #[test]
+        fn robot_stops_at_the_wall_and_reports_it() {
+            let mut s = walled_box(NoiseConfig::none());
+            s.set_robot_position(Vector3::new(2.5, 2.5, 0.));
+            s.set_wheel_velo(10., 10.);
+            s.step(0.5);
+            s.step(0.5);
+            assert!(s.take_collision().is_none());
+            // The footprint would reach into the wall at x = 4.
+            s.step(0.5);
+            let c = s.take_collision().unwrap();
+            assert_eq!(c.wall, Vector2::new(4., 2.));
+            assert_eq!(c.pose, Vector3::new(3.5, 2.5, 0.));
+            assert_eq!(s.robot_position(), c.pose);
+            assert_eq!(s.get_wheel_velo(), (0., 0.));
+            assert!(s.take_collision().is_none());
+        }
+
+        #[test]
+        fn odometry_drifts_away_from_the_real_pose() {
+            let mut s = walled_box(NoiseConfig::none());
+            s.set_robot_position(Vector3::new(1.5, 1.5, 0.));
+            s.set_wheel_velo(5., 6.);
+            for _ in 0..10 {
+                s.step(0.1);
+            }
+            // Without noise both are the same.
+            assert_eq!(s.robot_position(), s.odometry_position());
+            let exact = s.odometry_position();
+            s.set_odometry_noise(OdometryNoise::default());
+            s.set_robot_position(Vector3::new(1.5, 1.5, 0.));
+            for _ in 0..10 {
+                s.step(0.1);
+            }
+            // The odometry only sees the wheels, the real robot slips.
+            assert!((s.odometry_position() - exact).norm() < 1e-9);
+            assert!((s.robot_position() - exact).norm() > 1e-6);
+        }
+
+        fn sim(map: Vec<Vector2<f64>>) -> MyPiSim {
+            MyPiSim::new(5., map, NoiseConfig::none())
+        }
+
+        fn sorted(mut v: Vec<Vector2<f64>>) -> Vec<Vector2<f64>> {
+            v.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+            v.dedup();
+            v
+        }
+
+        #[test]
+        fn set_map_marks_walls_and_includes_max() {
+            let s = sim(vec![Vector2::new(0., 0.), Vector2::new(3., 2.)]);
+            assert_eq!(s.map.width(), 4);
+            assert_eq!(s.map.height(), 3);
+            assert_eq!(s.map.get(0, 0), PositionType::Wall);
+            assert_eq!(s.map.get(3, 2), PositionType::Wall);
+            assert_eq!(s.map.get(1, 1), PositionType::Empty);
+        }
+
+        #[test]
+        fn map_round_trips_with_offset_and_negative_coordinates() {
+            let map = vec![
+                Vector2::new(-3., 7.),
+                Vector2::new(2., -1.),
+                Vector2::new(-3., -1.),
+                Vector2::new(0., 4.),
+            ];
+            let s = sim(map.clone());
+            assert_eq!(s.map.origin(), Vector2::new(-3., -1.));
+            assert_eq!(s.get_map(), sorted(map));
+        }
+
+        #[test]
+        fn map_round_trips_small_world() {
+            let map = crate::app::small_map();
+            let mut s = sim(Vec::new());
+            assert!(s.get_map().is_empty());
+            s.set_map(map.clone());
+            assert_eq!(s.get_map(), sorted(map));
+        }
+
+        #[test]
+        fn ray_hits_wall_with_offset_origin() {
+            let map = (-2..=2)
+                .flat_map(|y| [Vector2::new(5., y as f64), Vector2::new(10., y as f64)])
+                .collect();
+            let s = sim(map);
+            let origin = Vector2::new(7.5, 0.5);
+            let cast = |angle| s.map.cast_ray(origin, angle, s.max_range).map(|h| h.dist);
+            assert_eq!(cast(0.), Some(2.5));
+            assert_eq!(cast(std::f64::consts::PI), Some(1.5));
+            // Straight up there is no wall, the ray leaves the map.
+            assert_eq!(cast(std::f64::consts::FRAC_PI_2), None);
+        }
     }
 }
 #[derive(Debug)]
@@ -235,7 +1041,7 @@ pub enum MyPi {
 }   
 // Here we forward the call to a secific struct that is inside the enum.
 impl RobotAccess for MyPi {
-    fn get_hcsr04_dist(&mut self) -> Option<f64> {
+    fn get_hcsr04_dist(&mut self) -> Result<Option<f64>, SensorError> {
         match self {
             MyPi::Real(my_pi_real) => my_pi_real.get_hcsr04_dist(),
             MyPi::Sim(my_pi_sim) => my_pi_sim.get_hcsr04_dist(),
@@ -270,19 +1076,19 @@ impl RobotAccess for MyPi {
         }
     }
     
-    fn set_robot_belief(&mut self, robot_start_belief: RobotStartBelief) {
-        match self {
-            MyPi::Real(my_pi_real) => my_pi_real.set_robot_belief(robot_start_belief),
-            MyPi::Sim(my_pi_sim) => my_pi_sim.set_robot_belief(robot_start_belief),
-        }
-    }
-    
     fn robot_position(&self) -> Vector3<f64> {
         match self {
             MyPi::Real(my_pi_real) => my_pi_real.robot_position(),
             MyPi::Sim(my_pi_sim) => my_pi_sim.robot_position(),
         }
     }
+
+    fn odometry_position(&self) -> Vector3<f64> {
+        match self {
+            MyPi::Real(my_pi_real) => my_pi_real.odometry_position(),
+            MyPi::Sim(my_pi_sim) => my_pi_sim.odometry_position(),
+        }
+    }
     
     fn wheel_velo_max(&self) -> f64 {
         match self {
@@ -311,5 +1117,99 @@ impl RobotAccess for MyPi {
             MyPi::Sim(my_pi_sim) => my_pi_sim.get_wheel_velo(),
         }
     }
+
+    fn get_measured_wheel_velo(&self) -> (f64, f64) {
+        match self {
+            MyPi::Real(my_pi_real) => my_pi_real.get_measured_wheel_velo(),
+            MyPi::Sim(my_pi_sim) => my_pi_sim.get_measured_wheel_velo(),
+        }
+    }
+
+    fn step(&mut self, dt: f64) {
+        match self {
+            MyPi::Real(my_pi_real) => my_pi_real.step(dt),
+            MyPi::Sim(my_pi_sim) => my_pi_sim.step(dt),
+        }
+    }
+
+    fn take_collision(&mut self) -> Option<Collision> {
+        match self {
+            MyPi::Real(my_pi_real) => my_pi_real.take_collision(),
+            MyPi::Sim(my_pi_sim) => my_pi_sim.take_collision(),
+        }
+    }
+    fn take_motor_errors(&mut self) -> (usize, Option<String>) {
+        match self {
+            MyPi::Real(my_pi_real) => my_pi_real.take_motor_errors(),
+            MyPi::Sim(my_pi_sim) => my_pi_sim.take_motor_errors(),
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    #[test]
+    fn diff_drive_straight_on_the_spot_and_arc() {
+        let d = DiffDrive { wheel_base: 0.5, wheel_radius: 0.1 };
+        // 10 rad/s on both wheels is 1 m/s straight ahead.
+        let p = d.integrate(Vector3::new(1., 2., FRAC_PI_2), 10., 10., 2.);
+        assert!((p - Vector3::new(1., 4., FRAC_PI_2)).norm() < 1e-9, "{}", p);
+        // Opposite wheels turn on the spot with 2 rad/s.
+        let p = d.integrate(Vector3::zeros(), -5., 5., FRAC_PI_4);
+        assert!((p - Vector3::new(0., 0., FRAC_PI_2)).norm() < 1e-9, "{}", p);
+        // 1 m/s and 2 rad/s is a circle with radius 0.5, a quarter of it here.
+        let p = d.integrate(Vector3::zeros(), 5., 15., FRAC_PI_4);
+        assert!((p - Vector3::new(0.5, 0.5, FRAC_PI_2)).norm() < 1e-9, "{}", p);
+        assert!((normalize_angle(3. * PI) + PI).abs() < 1e-9);
+    }
+
+    #[test]
+    fn odometry_deltas_turn_drive_turn() {
+        let (rot1, trans, rot2) = odometry_deltas(Vector3::new(0., 0., 0.), Vector3::new(1., 1., PI));
+        assert!((rot1 - FRAC_PI_4).abs() < 1e-9);
+        assert!((trans - 2_f64.sqrt()).abs() < 1e-9);
+        assert!((rot2 - 3. * FRAC_PI_4).abs() < 1e-9);
+        // Backwards is a negative translation and no turn.
+        let (rot1, trans, rot2) = odometry_deltas(Vector3::new(0., 0., 0.), Vector3::new(-2., 0., 0.));
+        assert_eq!((rot1, trans, rot2), (0., -2., 0.));
+        // Without noise the sample is the motion itself, applied to the other pose.
+        let mut rng = rand::thread_rng();
+        let p = OdometryNoise::none().sample(&mut rng, Vector3::zeros(), Vector3::new(1., 0., 0.), Vector3::new(0., 0., FRAC_PI_2));
+        assert!((p - Vector3::new(0., 1., FRAC_PI_2)).norm() < 1e-9, "{}", p);
+    }
+
+    #[test]
+    fn retry_policy_stops_at_first_success() {
+        let policy = RetryPolicy { retries: 3, delay: std::time::Duration::ZERO, factor: 2., budget: std::time::Duration::from_secs(1) };
+        let mut calls = 0;
+        let res: Result<u32, SensorError> = policy.run(|| {
+            calls += 1;
+            if calls < 3 { Err(SensorError::Timeout) } else { Ok(calls) }
+        });
+        assert_eq!(res, Ok(3));
+        calls = 0;
+        let res: Result<u32, SensorError> = policy.run(|| {
+            calls += 1;
+            Err(SensorError::OutOfRange(calls as f64))
+        });
+        // One try and three retries.
+        assert_eq!(res, Err(SensorError::OutOfRange(4.)));
+    }
+
+    #[test]
+    fn retry_policy_stays_within_the_budget() {
+        let policy = RetryPolicy { retries: 5, delay: std::time::Duration::ZERO, factor: 1., budget: std::time::Duration::from_millis(50) };
+        let mut calls = 0;
+        let res: Result<u32, SensorError> = policy.run(|| {
+            calls += 1;
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            Err(SensorError::Timeout)
+        });
+        // A third try of 20 ms would end after 60 ms.
+        assert_eq!(res, Err(SensorError::Timeout));
+        assert_eq!(calls, 2);
+    }
+}
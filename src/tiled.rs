@@ -0,0 +1,274 @@
+use std::path::Path;
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::app::{Marker, MarkerKind, World};
+
+// Maps drawn in Tiled (https://www.mapeditor.org), read at runtime. Only finite maps with CSV
+// encoded layers, that is what Tiled writes by default.
+// The tile in row r (counted from the top) and column c is the cell (height - 1 - r, c), the
+// same as create_map.py made of it, so the maps line up with WALL_SMALL and WALL_BIG.
+//
+// Start poses, goals and landmarks come from marker tiles and from objects on object layers.
+// The kind of an object is its class (type in older Tiled versions) or else the name of its
+// layer: start, goal or landmark, plurals too. Other objects are left alone. An object can
+// have a float property heading in rad, in map coordinates like the rest.
+
+// Tile id of the walls in the tilesets in assets.
+pub const DEFAULT_WALL_IDS: &[u32] = &[5];
+// Tile id of the start marker.
+pub const DEFAULT_START_IDS: &[u32] = &[45];
+// Tiled keeps the flips and rotations of a tile in the top bits of its id.
+const TILE_ID_MASK: u32 = 0x1FFF_FFFF;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    // Tile ids as tiles[row][col], 0 is an empty tile.
+    pub tiles: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledMap {
+    pub width: usize,
+    pub height: usize,
+    pub layers: Vec<TileLayer>,
+    // Markers from the object layers. The marker tiles are in the layers.
+    pub objects: Vec<Marker>,
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Read(String, std::io::Error),
+    Xml(String, roxmltree::Error),
+    // Valid XML, but not a map we can read.
+    Format(String, String),
+}
+
+impl std::fmt::Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::Read(path, e) => write!(f, "can't read map {}: {}", path, e),
+            MapError::Xml(path, e) => write!(f, "map {}: {}", path, e),
+            MapError::Format(path, e) => write!(f, "map {}: {}", path, e),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+fn attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T, String> {
+    let v = node.attribute(name).ok_or_else(|| format!("<{}> has no {}", node.tag_name().name(), name))?;
+    v.parse().map_err(|_| format!("<{}> has an invalid {}: {:?}", node.tag_name().name(), name, v))
+}
+
+fn parse_layer(node: roxmltree::Node) -> Result<TileLayer, String> {
+    let name = node.attribute("name").unwrap_or("").to_string();
+    let width: usize = attribute(node, "width")?;
+    let height: usize = attribute(node, "height")?;
+    let data = node.children()
+        .find(|n| n.has_tag_name("data"))
+        .ok_or_else(|| format!("layer {:?} has no data", name))?;
+    if data.attribute("encoding") != Some("csv") {
+        return Err(format!("layer {:?} is not CSV encoded, set the layer format to CSV in Tiled", name));
+    }
+    if data.children().any(|n| n.has_tag_name("chunk")) {
+        return Err(format!("layer {:?} is infinite, only finite maps are supported", name));
+    }
+    let ids = data.text().unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u32>().map(|id| id & TILE_ID_MASK).map_err(|_| format!("layer {:?} has an invalid tile id {:?}", name, s)))
+        .collect::<Result<Vec<u32>, String>>()?;
+    if ids.len() != width * height {
+        return Err(format!("layer {:?} has {} tiles, expected {} x {}", name, ids.len(), width, height));
+    }
+    Ok(TileLayer { name, tiles: ids.chunks(width).map(|r| r.to_vec()).collect() })
+}
+
+fn marker_kind(s: &str) -> Option<MarkerKind> {
+    match s.trim().to_lowercase().as_str() {
+        "start" | "starts" => Some(MarkerKind::Start),
+        "goal" | "goals" => Some(MarkerKind::Goal),
+        "landmark" | "landmarks" => Some(MarkerKind::Landmark),
+        _ => None,
+    }
+}
+
+// Markers of one object layer. Positions are in pixels from the top left corner of the map.
+fn parse_objects(group: roxmltree::Node, tile_size: Vector2<f64>, height: usize) -> Result<Vec<Marker>, String> {
+    let group_kind = group.attribute("name").and_then(marker_kind);
+    let mut markers = Vec::new();
+    for object in group.children().filter(|n| n.has_tag_name("object")) {
+        let kind = match object.attribute("class").or(object.attribute("type")) {
+            Some(c) if !c.is_empty() => marker_kind(c),
+            _ => group_kind,
+        };
+        let Some(kind) = kind else { continue };
+        let number = |name: &str| match object.attribute(name) {
+            Some(_) => attribute::<f64>(object, name),
+            None => Ok(0.),
+        };
+        let (x, y, w, h) = (number("x")?, number("y")?, number("width")?, number("height")?);
+        // Rectangles and ellipses count with their center. Tile objects hang up from x, y.
+        let (px, py) = match object.attribute("gid") {
+            Some(_) => (x + w / 2., y - h / 2.),
+            None => (x + w / 2., y + h / 2.),
+        };
+        let heading = object.children()
+            .filter(|n| n.has_tag_name("properties"))
+            .flat_map(|n| n.children())
+            .find(|n| n.has_tag_name("property") && n.attribute("name") == Some("heading"))
+            .map(|n| attribute::<f64>(n, "value"))
+            .transpose()?
+            .unwrap_or(0.);
+        let name = match object.attribute("name") {
+            Some(n) if !n.is_empty() => n.to_string(),
+            _ => format!("{:?} {}", kind, object.attribute("id").unwrap_or("?")).to_lowercase(),
+        };
+        // Row r and column c of the tiles become the cell (height - 1 - r, c).
+        let pose = Vector3::new(height as f64 - py / tile_size.y, px / tile_size.x, heading);
+        markers.push(Marker { name, kind, pose });
+    }
+    Ok(markers)
+}
+
+impl TiledMap {
+    pub fn load(path: &Path) -> Result<Self, MapError> {
+        let p = path.display().to_string();
+        let text = std::fs::read_to_string(path).map_err(|e| MapError::Read(p.clone(), e))?;
+        let doc = roxmltree::Document::parse(&text).map_err(|e| MapError::Xml(p.clone(), e))?;
+        TiledMap::from_document(&doc).map_err(|e| MapError::Format(p, e))
+    }
+
+    #[cfg(test)]
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let doc = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+        TiledMap::from_document(&doc)
+    }
+
+    fn from_document(doc: &roxmltree::Document) -> Result<Self, String> {
+        let root = doc.root_element();
+        if !root.has_tag_name("map") {
+            return Err(format!("expected <map>, found <{}>", root.tag_name().name()));
+        }
+        if root.attribute("orientation").is_some_and(|o| o != "orthogonal") {
+            return Err("only orthogonal maps are supported".to_string());
+        }
+        let width: usize = attribute(root, "width")?;
+        let height: usize = attribute(root, "height")?;
+        let layers = root.children()
+            .filter(|n| n.has_tag_name("layer"))
+            .map(parse_layer)
+            .collect::<Result<Vec<TileLayer>, String>>()?;
+        if let Some(l) = layers.iter().find(|l| l.tiles.len() != height || l.tiles[0].len() != width) {
+            return Err(format!("layer {:?} is not the size of the map", l.name));
+        }
+        let tile_size = Vector2::new(attribute(root, "tilewidth").unwrap_or(1.), attribute(root, "tileheight").unwrap_or(1.));
+        let mut objects = Vec::new();
+        for group in root.children().filter(|n| n.has_tag_name("objectgroup")) {
+            objects.extend(parse_objects(group, tile_size, height)?);
+        }
+        Ok(TiledMap { width, height, layers, objects })
+    }
+
+    // Lower left corner of the cell of the tile in row and col.
+    pub fn cell(&self, row: usize, col: usize) -> Vector2<f64> {
+        Vector2::new((self.height - 1 - row) as f64, col as f64)
+    }
+
+    // Cells that have one of the tile ids on any layer.
+    pub fn cells_with(&self, ids: &[u32]) -> Vec<Vector2<f64>> {
+        let mut cells = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.layers.iter().any(|l| ids.contains(&l.tiles[row][col])) {
+                    cells.push(self.cell(row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn walls(&self, wall_ids: &[u32]) -> Vec<Vector2<f64>> {
+        self.cells_with(wall_ids)
+    }
+
+    // Start markers from the marker tiles, named start 1, start 2, ... from the top left, and
+    // the markers from the object layers.
+    pub fn markers(&self, start_ids: &[u32]) -> Vec<Marker> {
+        let tiles = self.cells_with(start_ids).into_iter().enumerate().map(|(i, c)| Marker {
+            name: format!("start {}", i + 1),
+            kind: MarkerKind::Start,
+            pose: Vector3::new(c.x + 0.5, c.y + 0.5, 0.),
+        });
+        tiles.chain(self.objects.iter().cloned()).collect()
+    }
+
+    // The walls and markers as the world the app shows under name.
+    pub fn world<'a>(&self, name: &'a str, wall_ids: &[u32], start_ids: &[u32]) -> World<'a> {
+        let mut world = World::new(name, None, self.walls(wall_ids));
+        world.markers = self.markers(start_ids);
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_map_matches_the_generated_walls() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/map_small.tmx");
+        let map = TiledMap::load(&path).unwrap();
+        assert_eq!((map.width, map.height), (40, 40));
+        let mut walls = map.walls(DEFAULT_WALL_IDS);
+        let mut expected = crate::app::small_map();
+        let key = |v: &Vector2<f64>| (v.x as i64, v.y as i64);
+        walls.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(walls, expected);
+        let world = map.world("small", DEFAULT_WALL_IDS, DEFAULT_START_IDS);
+        assert_eq!(world.wall_list.len(), expected.len());
+    }
+
+    #[test]
+    fn reads_csv_layers_and_rejects_the_rest() {
+        let tmx = |data: &str| format!(r#"<map orientation="orthogonal" width="3" height="2">
+            <layer name="walls" width="3" height="2"><data encoding="csv">{}</data></layer></map>"#, data);
+        // The flipped wall still counts as a wall.
+        let map = TiledMap::parse(&tmx("5,0,7,\n0,2147483653,0")).unwrap();
+        assert_eq!(map.walls(&[5]), vec![Vector2::new(1., 0.), Vector2::new(0., 1.)]);
+        assert_eq!(map.walls(&[5, 7]).len(), 3);
+        assert!(TiledMap::parse(&tmx("5,0,7")).unwrap_err().contains("has 3 tiles"));
+        let base64 = r#"<map width="1" height="1"><layer name="l" width="1" height="1"><data encoding="base64">BQAAAA==</data></layer></map>"#;
+        assert!(TiledMap::parse(base64).unwrap_err().contains("not CSV"));
+    }
+
+    #[test]
+    fn markers_from_tiles_and_objects() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/map_small.tmx");
+        let map = TiledMap::load(&path).unwrap();
+        let world = map.world("map_small", DEFAULT_WALL_IDS, DEFAULT_START_IDS);
+        let start = world.start(None).unwrap();
+        assert_eq!((start.name.as_str(), start.pose), ("start 1", Vector3::new(35.5, 12.5, 0.)));
+
+        // 2 x 2 tiles of 16 px. The goal is a point in the lower right tile, the rectangle in the
+        // layer "landmarks" covers the upper left tile.
+        let tmx = r#"<map width="2" height="2" tilewidth="16" tileheight="16">
+            <layer name="l" width="2" height="2"><data encoding="csv">0,0,0,45</data></layer>
+            <objectgroup name="landmarks">
+              <object id="1" name="door" x="0" y="0" width="16" height="16"/>
+              <object id="2" type="goal" x="24" y="24"><properties><property name="heading" type="float" value="1.5"/></properties><point/></object>
+              <object id="3" class="something else" x="8" y="8"/>
+            </objectgroup></map>"#;
+        let markers = TiledMap::parse(tmx).unwrap().markers(&[45]);
+        let expected = vec![
+            Marker { name: "start 1".to_string(), kind: MarkerKind::Start, pose: Vector3::new(0.5, 1.5, 0.) },
+            Marker { name: "door".to_string(), kind: MarkerKind::Landmark, pose: Vector3::new(1.5, 0.5, 0.) },
+            Marker { name: "goal 2".to_string(), kind: MarkerKind::Goal, pose: Vector3::new(0.5, 1.5, 1.5) },
+        ];
+        assert_eq!(markers, expected);
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use rppal::gpio::{Gpio, InputPin, OutputPin, Trigger};
+
+// HC-SR04 read straight from the pins. The hc-sr04 crate busy-waits for the echo without a
+// deadline, so a sensor that is unplugged or never answers hangs the whole app. Here we wait
+// for the edges of the echo with poll_interrupt, which gives up after the timeout.
+
+// In m/s at 20 degree C.
+const SPEED_OF_SOUND: f64 = 343.;
+// The data sheet asks for at least 10 us.
+const TRIGGER_PULSE: Duration = Duration::from_micros(10);
+
+// The echo pulse covers the way to the wall and back.
+pub fn pulse_to_distance(pulse: Duration) -> f64 {
+    pulse.as_secs_f64() * SPEED_OF_SOUND / 2.
+}
+
+#[derive(Debug)]
+pub struct Ultrasonic {
+    trig: OutputPin,
+    echo: InputPin,
+}
+
+impl Ultrasonic {
+    pub fn new(gpio: &Gpio, trig: u8, echo: u8) -> Result<Self, rppal::gpio::Error> {
+        let trig = gpio.get(trig)?.into_output_low();
+        let mut echo = gpio.get(echo)?.into_input();
+        echo.set_interrupt(Trigger::Both, None)?;
+        Ok(Ultrasonic { trig, echo })
+    }
+
+    // Distance in m of one measurement. None if the echo didn't start and end within timeout.
+    // Without an obstacle the sensor still answers, with a pulse of about 38 ms.
+    pub fn measure(&mut self, timeout: Duration) -> Result<Option<f64>, rppal::gpio::Error> {
+        // Throw away old edges, the ones we want come after the trigger.
+        while self.echo.poll_interrupt(false, Some(Duration::ZERO))?.is_some() {}
+        let deadline = Instant::now() + timeout;
+        self.trig.set_high();
+        std::thread::sleep(TRIGGER_PULSE);
+        self.trig.set_low();
+        let mut rise = None;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Ok(None);
+            }
+            let Some(event) = self.echo.poll_interrupt(false, Some(left))? else {
+                return Ok(None);
+            };
+            match (event.trigger, rise) {
+                (Trigger::RisingEdge, _) => rise = Some(event.timestamp),
+                (Trigger::FallingEdge, Some(r)) => return Ok(Some(pulse_to_distance(event.timestamp.saturating_sub(r)))),
+                // A falling edge before the rising one is the end of an older pulse.
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_length_to_distance() {
+        // 1 m there and back.
+        assert!((pulse_to_distance(Duration::from_secs_f64(2. / SPEED_OF_SOUND)) - 1.).abs() < 1e-6);
+        assert_eq!(pulse_to_distance(Duration::ZERO), 0.);
+    }
+}